@@ -1,10 +1,12 @@
 use ggez::glam::Vec2;
 use rand::Rng;
 
-use crate::helpers::random_offscreen_position;
-use crate::SCREEN_SIZE;
+use crate::enemies::{BehaviourKind, EnemyCatalog};
+use crate::patterns::Bullet;
+use crate::screen_size;
+use crate::weapons::WeaponStats;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum TypeActor {
     Player,
     Enemy,
@@ -12,14 +14,18 @@ pub enum TypeActor {
     None,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Subtype {
     BasicEnemy,
     KamikazeEnemy,
+    InvaderEnemy,
     BossEnemy,
-    //TankEnemy,
+    TankEnemy,
     BasicShot,
+    LaserShot,
+    MissileShot,
     EnemyShot,
+    BossShot,
     AsteroidShot,
     BasicCountBuff,
     BasicReloadBuff,
@@ -33,10 +39,19 @@ pub struct Actor {
     pub subtag: Subtype,
     pub velocity: Vec2,
     pub position: Vec2,
+    pub previous_position: Vec2,
     pub desired_pos: Vec2,
     pub life_points: f32,
     pub box_size: f32,
     pub rotation: f32,
+    // index into the enemy catalog for regular enemies, into the weapon catalog for player shots
+    pub archetype: usize,
+    // taken off an enemy's life points on a hit, three times as much from a boss
+    pub damage: f32,
+    // the difficulty bonus an enemy spawned with, kamikazes steer faster with it
+    pub speed_bonus: f32,
+    // set for shots fired from a pattern, they fly along it instead of towards desired_pos
+    pub bullet: Option<Bullet>,
 }
 
 impl Actor {
@@ -46,10 +61,15 @@ impl Actor {
             subtag: Subtype::None,
             velocity: Vec2::ZERO,
             position: Vec2::new(-500.0, -500.0),
+            previous_position: Vec2::new(-500.0, -500.0),
             desired_pos: Vec2::ZERO,
             life_points: 0.0,
             box_size: 0.0,
             rotation: 0.0,
+            archetype: 0,
+            damage: 0.0,
+            speed_bonus: 0.0,
+            bullet: None,
         }
     }
 }
@@ -59,42 +79,58 @@ pub fn create_player() -> Actor {
         tag: TypeActor::Player,
         subtag: Subtype::None,
         velocity: Vec2::ZERO,
-        position: Vec2::new(SCREEN_SIZE.x / 2.0, SCREEN_SIZE.y / 2.0),
+        position: Vec2::new(screen_size().x / 2.0, screen_size().y / 2.0),
+        previous_position: Vec2::new(screen_size().x / 2.0, screen_size().y / 2.0),
         desired_pos: Vec2::ZERO,
         life_points: 1.0,
         box_size: 20.0,
         rotation: 0.0,
+        archetype: 0,
+        damage: 0.0,
+        speed_bonus: 0.0,
+        bullet: None,
     }
 }
 
-pub fn create_enemy(desired_pos: Vec2, starting_speed: f32, current_score: f32) -> Actor {
-    let mut rng = rand::thread_rng();
-
-    let random_choice = rng.gen_range(0..=1);
-    let position = random_offscreen_position(SCREEN_SIZE.x, SCREEN_SIZE.y);
-    let subtag: Subtype;
-    match random_choice {
-        0 => subtag = Subtype::BasicEnemy,
-        1 => subtag = Subtype::KamikazeEnemy,
-        _ => unreachable!(),
-    }
-    // using velocity for enemy speed
-    let velocity = Vec2::new(starting_speed, 0.0);
+pub fn create_enemy(
+    catalog: &EnemyCatalog,
+    archetype: usize,
+    position: Vec2,
+    desired_pos: Vec2,
+    speed_bonus: f32,
+    current_score: f32,
+) -> Actor {
+    let definition = catalog.get(archetype);
+    // using velocity for enemy speed, kamikazes start at rest and steer from there
+    let velocity = match definition.behaviour {
+        BehaviourKind::Kamikaze => Vec2::ZERO,
+        BehaviourKind::Basic => Vec2::new(definition.speed + speed_bonus, 0.0),
+    };
     Actor {
         tag: TypeActor::Enemy,
-        subtag,
+        subtag: definition.behaviour.subtype(),
         velocity,
         position,
+        previous_position: position,
         desired_pos,
-        life_points: 0.7 + current_score / 100.0,
-        box_size: 22.0,
+        life_points: definition.base_hp + current_score * definition.hp_per_score,
+        box_size: definition.hitbox,
         rotation: 0.0,
+        archetype,
+        damage: 0.0,
+        speed_bonus,
+        bullet: None,
     }
 }
 
-pub fn create_shot(position: Vec2, desired_pos: Vec2, mut subtag: Subtype) -> Actor {
+pub fn create_shot(
+    rng: &mut impl Rng,
+    position: Vec2,
+    desired_pos: Vec2,
+    mut subtag: Subtype,
+) -> Actor {
     let mut rotation = 0.0;
-    // using velocity to decide speed of shot
+    // using velocity to decide speed of shot (pixels per second)
     let mut velocity = Vec2::ZERO;
     let mut box_size = 1.0;
     match subtag {
@@ -102,11 +138,11 @@ pub fn create_shot(position: Vec2, desired_pos: Vec2, mut subtag: Subtype) -> Ac
             let direction = desired_pos - position;
             rotation = (direction.y as f64).atan2(direction.x as f64) as f32;
             rotation += std::f32::consts::FRAC_PI_2;
-            velocity.x = 15.0;
+            velocity.x = 900.0;
         }
         //uses Basic count buff as spawner for all other possible buffs
         Subtype::BasicCountBuff => {
-            let random_choice = rand::thread_rng().gen_range(0..=2);
+            let random_choice = rng.gen_range(0..=2);
 
             match random_choice {
                 0 => subtag = Subtype::BasicCountBuff,
@@ -114,11 +150,11 @@ pub fn create_shot(position: Vec2, desired_pos: Vec2, mut subtag: Subtype) -> Ac
                 2 => subtag = Subtype::BasicShieldBuff,
                 _ => unreachable!(),
             }
-            velocity.x = 2.0;
+            velocity.x = 120.0;
         }
         Subtype::AsteroidShot => {
             box_size = 15.0;
-            velocity.x = 2.0;
+            velocity.x = 120.0;
         }
         _ => (),
     }
@@ -128,25 +164,95 @@ pub fn create_shot(position: Vec2, desired_pos: Vec2, mut subtag: Subtype) -> Ac
         subtag,
         velocity,
         position,
+        previous_position: position,
         desired_pos,
         life_points: 15.0,
         box_size,
         rotation,
+        archetype: 0,
+        damage: 1.0,
+        speed_bonus: 0.0,
+        bullet: None,
     }
 }
 
-pub fn create_boss(subtag: Subtype) -> Actor {
-    let position = Vec2::new(SCREEN_SIZE.x / 2.0, -10.0);
-    let desired_pos = Vec2::new(SCREEN_SIZE.x / 2.0, 80.0);
+// a shot of one of the player's weapons, flying straight at `desired_pos`
+pub fn create_player_shot(
+    position: Vec2,
+    desired_pos: Vec2,
+    subtag: Subtype,
+    weapon: &WeaponStats,
+) -> Actor {
+    let direction = desired_pos - position;
+    let rotation = direction.y.atan2(direction.x) + std::f32::consts::FRAC_PI_2;
+    let box_size = match subtag {
+        Subtype::MissileShot => 6.0,
+        _ => 1.0,
+    };
+    Actor {
+        tag: TypeActor::Shot,
+        subtag,
+        velocity: Vec2::new(weapon.speed, 0.0),
+        position,
+        previous_position: position,
+        desired_pos,
+        life_points: 15.0,
+        box_size,
+        rotation,
+        archetype: 0,
+        damage: weapon.damage,
+        speed_bonus: 0.0,
+        bullet: None,
+    }
+}
+
+pub fn create_pattern_shot(bullet: Bullet, subtag: Subtype) -> Actor {
+    let direction = bullet.direction;
+    let rotation = direction.y.atan2(direction.x) + std::f32::consts::FRAC_PI_2;
+    Actor {
+        tag: TypeActor::Shot,
+        subtag,
+        velocity: Vec2::new(bullet.speed, 0.0),
+        position: bullet.origin,
+        previous_position: bullet.origin,
+        desired_pos: bullet.origin + direction * screen_size().length(),
+        life_points: 15.0 + bullet.delay,
+        box_size: 1.0,
+        rotation,
+        archetype: 0,
+        damage: 1.0,
+        speed_bonus: 0.0,
+        bullet: Some(bullet),
+    }
+}
+
+// where a boss flies in to before it starts moving on its own
+pub fn boss_entry() -> Vec2 {
+    Vec2::new(screen_size().x / 2.0, 80.0)
+}
+
+pub fn create_boss(subtag: Subtype, life_points: f32) -> Actor {
+    let position = Vec2::new(screen_size().x / 2.0, -10.0);
+    let desired_pos = boss_entry();
     let rotation = 0.0;
+    // the tank is bigger and crawls
+    let (speed, box_size) = match subtag {
+        Subtype::TankEnemy => (3.0, 30.0),
+        _ => (8.0, 20.0),
+    };
     Actor {
         tag: TypeActor::Enemy,
         subtag,
-        velocity: Vec2::new(8.0, 0.0),
+        velocity: Vec2::new(speed, 0.0),
         position,
+        previous_position: position,
         desired_pos,
-        life_points: 8.0,
-        box_size: 20.0,
+        life_points,
+        box_size,
         rotation,
+        archetype: 0,
+        damage: 0.0,
+        speed_bonus: 0.0,
+        bullet: None,
     }
 }
@@ -1,6 +1,6 @@
 use ggez::glam::Vec2;
 use ggez::graphics::{self, DrawParam, Image, Rect};
-use ggez::{Context, GameResult};
+use ggez::Context;
 use std::time::Duration;
 
 pub struct Animation {
@@ -15,15 +15,13 @@ pub struct Animation {
 
 impl Animation {
     pub fn new(
-        ctx: &mut Context,
-        path: &str,
+        sprite_sheet: Image,
         frame_count: usize,
         frame_time: Duration,
         position: Vec2,
-    ) -> GameResult<Animation> {
-        let sprite_sheet = Image::from_path(ctx, path)?;
+    ) -> Animation {
         let finished = false;
-        Ok(Self {
+        Self {
             sprite_sheet,
             frame_count,
             current_frame: 0,
@@ -31,7 +29,7 @@ impl Animation {
             elapsed_time: Duration::new(0, 0),
             position,
             finished,
-        })
+        }
     }
 
     pub fn update(&mut self, dt: Duration) {
@@ -1,14 +1,29 @@
 use ggez::glam::Vec2;
 use rand::Rng;
 
-use crate::actors::{create_shot, Actor, Subtype, TypeActor};
+use crate::actors::{Actor, Subtype, TypeActor};
+use crate::config::GameplayConfig;
+use crate::enemies::Steering;
 use crate::helpers::{make_rand_pos, position_to_direction, smoothstep};
-use crate::state::InputState;
-use crate::SCREEN_SIZE;
+use crate::patterns::Pattern;
+use crate::screen_size;
+use crate::simulation::InputState;
 
-pub fn update_player_position(player: &mut Actor, input: &mut InputState, dt: f32) {
-    let acceleration_rate = 12.0;
-    let deceleration_rate = 8.0;
+// the speed shots seeking the player have always had
+const ENEMY_SHOT_SPEED: f32 = 900.0;
+pub const TANK_FIRE_INTERVAL: f32 = 2.5;
+pub const TANK_SPREAD_SHOTS: usize = 7;
+// about 100 degrees from the first shot of a spread to the last
+pub const TANK_SPREAD_ANGLE: f32 = 1.75;
+
+pub fn update_player_position(
+    player: &mut Actor,
+    input: &mut InputState,
+    gameplay: &GameplayConfig,
+    dt: f32,
+) {
+    let acceleration_rate = gameplay.player_acceleration;
+    let deceleration_rate = gameplay.player_deceleration;
     if input.velocity.length() > 0.0 {
         player.velocity = player.velocity.lerp(input.velocity, acceleration_rate * dt);
     } else {
@@ -21,6 +36,10 @@ pub fn update_player_position(player: &mut Actor, input: &mut InputState, dt: f3
 
 pub fn update_basic_enemy_movement(enemies: &mut Vec<Actor>, dt: f32) {
     for enemy in enemies {
+        // steered in kamikaze_behaviour instead
+        if let Subtype::KamikazeEnemy = enemy.subtag {
+            continue;
+        }
         let enemy_speed = enemy.velocity.x;
         let t = smoothstep(enemy_speed * dt);
         enemy.position = enemy.position.lerp(enemy.desired_pos, t);
@@ -28,9 +47,14 @@ pub fn update_basic_enemy_movement(enemies: &mut Vec<Actor>, dt: f32) {
 }
 pub fn update_shot_movement(shots: &mut Vec<Actor>, dt: f32) {
     for shot in shots {
+        if let Some(bullet) = &mut shot.bullet {
+            shot.position = bullet.advance(dt);
+            shot.life_points -= dt;
+            continue;
+        }
         let shot_speed = shot.velocity.x;
         let direction = position_to_direction(shot.position, shot.desired_pos);
-        shot.position += direction * shot_speed;
+        shot.position += direction * shot_speed * dt;
         shot.life_points -= dt;
         match shot.subtag {
             Subtype::BasicReloadBuff | Subtype::BasicCountBuff => shot.rotation += dt,
@@ -43,6 +67,7 @@ pub fn boss_enemy_behaviour(
     enemy: &mut Actor,
     player: &mut Actor,
     shots: &mut Vec<Actor>,
+    rng: &mut impl Rng,
     dt: f32,
 ) {
     // velocity.y is used for the speed of attacks of the boss
@@ -51,39 +76,130 @@ pub fn boss_enemy_behaviour(
     enemy.rotation = direction.y.atan2(direction.x) - std::f32::consts::FRAC_PI_2;
     let distance = (enemy.desired_pos - enemy.position).length();
     if (distance < 1.5) & (enemy.velocity.y >= enemy.velocity.x) {
-        let mut rng = rand::thread_rng();
         let random_choice = rng.gen_range(0..=1);
         match random_choice {
             0 => {
                 enemy.desired_pos = enemy.position
                     + direction.normalize() * ((player.position - enemy.position).length() * 1.2);
             }
-            1 => {
+            // the twin cannons, or a volley every other time
+            1 if rng.gen_bool(0.5) => {
                 let source_pos = Vec2::new(enemy.position.x - 25.0, enemy.position.y + 3.0);
                 shoot_at_player(source_pos, player, shots);
                 let source_pos = Vec2::new(enemy.position.x + 25.0, enemy.position.y + 3.0);
                 shoot_at_player(source_pos, player, shots);
             }
+            1 => {
+                let source_pos = Vec2::new(enemy.position.x, enemy.position.y + 20.0);
+                boss_pattern(rng).fire(source_pos, player.position, Subtype::BossShot, shots);
+            }
             _ => unreachable!(),
         }
         enemy.velocity.y = 0.0;
     }
 }
-pub fn basic_enemy_behaviour(enemy: &mut Actor, player: &mut Actor, shots: &mut Vec<Actor>) {
+// the tank crawls between spots near the top and fires a wide fan every few seconds
+pub fn tank_enemy_behaviour(
+    enemy: &mut Actor,
+    player: &mut Actor,
+    shots: &mut Vec<Actor>,
+    rng: &mut impl Rng,
+    dt: f32,
+) {
+    // velocity.y counts the seconds since the last spread
+    enemy.velocity.y += dt;
+    let distance = (enemy.desired_pos - enemy.position).length();
+    if distance < 1.5 {
+        let x = rng.gen_range(100.0..screen_size().x - 100.0);
+        let y = rng.gen_range(60.0..160.0);
+        enemy.desired_pos = Vec2::new(x, y);
+    }
+    if enemy.velocity.y >= TANK_FIRE_INTERVAL {
+        let source_pos = Vec2::new(enemy.position.x, enemy.position.y + 20.0);
+        let spread = Pattern::AimedFan {
+            count: TANK_SPREAD_SHOTS,
+            spread: TANK_SPREAD_ANGLE,
+            speed: ENEMY_SHOT_SPEED,
+        };
+        spread.fire(source_pos, player.position, Subtype::EnemyShot, shots);
+        enemy.velocity.y = 0.0;
+    }
+}
+// turns towards the target no faster than the turn rate while speeding up, so a late sidestep
+// makes it overshoot. velocity is the real velocity here, the sprite faces along it
+pub fn kamikaze_behaviour(enemy: &mut Actor, target: Vec2, steering: &Steering, dt: f32) {
+    let wanted = position_to_direction(enemy.position, target);
+    let speed = enemy.velocity.length();
+    let mut heading = if speed > 0.0 {
+        enemy.velocity / speed
+    } else {
+        wanted
+    };
+    if wanted != Vec2::ZERO && heading != Vec2::ZERO {
+        let max_turn = steering.turn_rate * dt;
+        let turn = heading.angle_between(wanted).clamp(-max_turn, max_turn);
+        heading = Vec2::from_angle(turn).rotate(heading);
+    }
+    let speed = (speed + steering.acceleration * dt).min(steering.max_speed);
+    enemy.velocity = heading * speed;
+    enemy.position += enemy.velocity * dt;
+    if heading != Vec2::ZERO {
+        enemy.rotation = heading.y.atan2(heading.x) - std::f32::consts::FRAC_PI_2;
+    }
+}
+pub fn basic_enemy_behaviour(
+    enemy: &mut Actor,
+    player: &mut Actor,
+    shots: &mut Vec<Actor>,
+    rng: &mut impl Rng,
+) {
     let distance = (enemy.desired_pos - enemy.position).length();
 
     if distance < 0.05 {
         let source_pos = Vec2::new(enemy.position.x, enemy.position.y + 3.0);
         shoot_at_player(source_pos, player, shots);
-        let destination = make_rand_pos();
+        let destination = make_rand_pos(rng);
         enemy.desired_pos = destination;
     }
 }
 
 fn shoot_at_player(source_pos: Vec2, player: &mut Actor, shots: &mut Vec<Actor>) {
-    let mut dest_pos = Vec2::new(player.position.x, player.position.y);
-    let direction = position_to_direction(source_pos, dest_pos);
-    dest_pos += direction * SCREEN_SIZE;
-    let shot = create_shot(source_pos, dest_pos, Subtype::EnemyShot);
-    shots.push(shot);
+    let aimed = Pattern::AimedFan {
+        count: 1,
+        spread: 0.0,
+        speed: ENEMY_SHOT_SPEED,
+    };
+    aimed.fire(source_pos, player.position, Subtype::EnemyShot, shots);
+}
+
+// one of the volleys of the purple boss, all in its own shots
+fn boss_pattern(rng: &mut impl Rng) -> Pattern {
+    match rng.gen_range(0..4) {
+        0 => Pattern::Radial {
+            count: 16,
+            speed: 250.0,
+        },
+        1 => Pattern::Spiral {
+            count: 24,
+            speed: 300.0,
+            angular_velocity: 6.0,
+            interval: 0.05,
+        },
+        2 => Pattern::SineStream {
+            count: 6,
+            speed: 350.0,
+            interval: 0.15,
+            amplitude: 30.0,
+            frequency: 1.5,
+        },
+        3 => Pattern::Delayed {
+            delay: 0.6,
+            pattern: Box::new(Pattern::AimedFan {
+                count: 5,
+                spread: 0.8,
+                speed: 450.0,
+            }),
+        },
+        _ => unreachable!(),
+    }
 }
@@ -0,0 +1,107 @@
+use ggez::glam::Vec2;
+
+use crate::screen_size;
+
+// cells of the occupancy mask, each drawn as a square of BUNKER_PIXEL screen pixels
+pub const BUNKER_WIDTH: usize = 22;
+pub const BUNKER_HEIGHT: usize = 16;
+pub const BUNKER_PIXEL: f32 = 4.0;
+pub const BUNKER_COUNT: usize = 4;
+// in cells, every hit clears a round hole this size
+const CRATER_RADIUS: f32 = 2.5;
+const BUNKER_COLOR: [u8; 4] = [90, 220, 90, 255];
+
+// a shield that erodes wherever a shot hits it, all in mask cells so it works without a window
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bunker {
+    // top left corner on screen
+    pub position: Vec2,
+    // row by row, true where the bunker is still solid
+    pub mask: Vec<bool>,
+    // set whenever the mask changed, the renderer uploads it again and clears this
+    pub changed: bool,
+}
+
+impl Bunker {
+    // the classic shape: bevelled top corners and an arch cut out of the bottom
+    pub fn new(position: Vec2) -> Bunker {
+        let mut mask = vec![true; BUNKER_WIDTH * BUNKER_HEIGHT];
+        for y in 0..BUNKER_HEIGHT {
+            for x in 0..BUNKER_WIDTH {
+                let from_edge = x.min(BUNKER_WIDTH - 1 - x);
+                let bevel = y + from_edge < 4;
+                let arch = y >= BUNKER_HEIGHT - 5 && (7..BUNKER_WIDTH - 7).contains(&x);
+                if bevel || arch {
+                    mask[y * BUNKER_WIDTH + x] = false;
+                }
+            }
+        }
+        Bunker {
+            position,
+            mask,
+            changed: true,
+        }
+    }
+
+    pub fn is_solid(&self, x: usize, y: usize) -> bool {
+        x < BUNKER_WIDTH && y < BUNKER_HEIGHT && self.mask[y * BUNKER_WIDTH + x]
+    }
+
+    fn cell_at(&self, point: Vec2) -> Option<(usize, usize)> {
+        let local = (point - self.position) / BUNKER_PIXEL;
+        if local.x < 0.0 || local.y < 0.0 {
+            return None;
+        }
+        let (x, y) = (local.x as usize, local.y as usize);
+        self.is_solid(x, y).then_some((x, y))
+    }
+
+    // the first solid cell on the way from `from` to `to`, so fast shots can't skip through
+    pub fn hit(&self, from: Vec2, to: Vec2) -> Option<(usize, usize)> {
+        let samples = ((to - from).length() / (BUNKER_PIXEL / 2.0))
+            .ceil()
+            .max(1.0) as usize;
+        (0..=samples).find_map(|i| self.cell_at(from.lerp(to, i as f32 / samples as f32)))
+    }
+
+    // clears a round crater around a cell, returns how many cells were removed
+    pub fn carve(&mut self, x: usize, y: usize) -> usize {
+        let reach = CRATER_RADIUS.ceil() as isize;
+        let mut removed = 0;
+        for dy in -reach..=reach {
+            for dx in -reach..=reach {
+                if ((dx * dx + dy * dy) as f32).sqrt() > CRATER_RADIUS {
+                    continue;
+                }
+                let (cx, cy) = (x as isize + dx, y as isize + dy);
+                if cx < 0 || cy < 0 || !self.is_solid(cx as usize, cy as usize) {
+                    continue;
+                }
+                self.mask[cy as usize * BUNKER_WIDTH + cx as usize] = false;
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            self.changed = true;
+        }
+        removed
+    }
+
+    // RGBA pixels of the mask, one per cell
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.mask
+            .iter()
+            .flat_map(|&solid| if solid { BUNKER_COLOR } else { [0; 4] })
+            .collect()
+    }
+}
+
+// spread evenly across the screen above the player
+pub fn create_bunkers() -> Vec<Bunker> {
+    let width = BUNKER_WIDTH as f32 * BUNKER_PIXEL;
+    let gap = (screen_size().x - BUNKER_COUNT as f32 * width) / (BUNKER_COUNT + 1) as f32;
+    let y = screen_size().y - 260.0;
+    (0..BUNKER_COUNT)
+        .map(|i| Bunker::new(Vec2::new(gap + i as f32 * (width + gap), y)))
+        .collect()
+}
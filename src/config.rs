@@ -0,0 +1,246 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::controls::LAYOUTS;
+use crate::data::DataError;
+
+pub const CONFIG_FILE: &str = "config.toml";
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub width: f32,
+    pub height: f32,
+    pub vsync: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        WindowConfig {
+            width: 1200.0,
+            height: 1000.0,
+            vsync: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameplayConfig {
+    // used when no --seed is given
+    pub seed: Option<u64>,
+    pub player_speed: f32,
+    pub player_acceleration: f32,
+    pub player_deceleration: f32,
+    pub boss_life_points: f32,
+    pub tank_life_points: f32,
+    // cooldown every reload pickup takes off each weapon, and shield points per shield pickup
+    pub reload_buff: f32,
+    pub shield_buff: f32,
+    pub max_life_points: f32,
+    // ships per run, and seconds without damage after every hit and respawn
+    pub lives: u32,
+    pub invulnerability_time: f32,
+    // seconds between two falling pickups and two asteroids
+    pub pickup_interval: f32,
+    pub asteroid_interval: f32,
+}
+
+impl Default for GameplayConfig {
+    fn default() -> Self {
+        GameplayConfig {
+            seed: None,
+            player_speed: 600.0,
+            player_acceleration: 12.0,
+            player_deceleration: 8.0,
+            boss_life_points: 8.0,
+            tank_life_points: 20.0,
+            reload_buff: 0.02,
+            shield_buff: 1.0,
+            max_life_points: 4.0,
+            lives: 3,
+            invulnerability_time: 2.0,
+            pickup_interval: 5.0,
+            asteroid_interval: 9.0,
+        }
+    }
+}
+
+// where player shots go: straight up, toward the mouse cursor or along the right stick
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AimMode {
+    Fixed,
+    Mouse,
+    Stick,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ControlsConfig {
+    // key binding preset used until the player has a bindings file of their own
+    pub layout: String,
+    // how far the left stick has to be tilted (0 to 1) before the ship moves
+    pub stick_deadzone: f32,
+    pub aim: AimMode,
+}
+
+impl Default for ControlsConfig {
+    fn default() -> Self {
+        ControlsConfig {
+            layout: "wasd".to_string(),
+            stick_deadzone: 0.2,
+            aim: AimMode::Fixed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioConfig {
+    pub master_volume: f32,
+    // sound effects only, on top of the master volume
+    pub sfx_volume: f32,
+    pub muted: bool,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        AudioConfig {
+            master_volume: 1.0,
+            sfx_volume: 0.8,
+            muted: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub window: WindowConfig,
+    pub gameplay: GameplayConfig,
+    pub controls: ControlsConfig,
+    pub audio: AudioConfig,
+}
+
+impl GameConfig {
+    // a missing file means defaults, `overrides` are `section.key=value` pairs from the command line
+    pub fn load(path: &Path, overrides: &[String]) -> Result<GameConfig, DataError> {
+        let file = path.display().to_string();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(DataError {
+                    file,
+                    line: None,
+                    message: e.to_string(),
+                })
+            }
+        };
+        GameConfig::from_toml(&file, &text, overrides)
+    }
+
+    pub fn from_toml(
+        file: &str,
+        text: &str,
+        overrides: &[String],
+    ) -> Result<GameConfig, DataError> {
+        let mut value: toml::Value = toml::from_str(text).map_err(|e| DataError {
+            file: file.to_string(),
+            line: e.line_col().map(|(line, _)| line + 1),
+            message: e.to_string(),
+        })?;
+        for assignment in overrides {
+            apply_override(&mut value, assignment).map_err(|message| DataError {
+                file: "command line".to_string(),
+                line: None,
+                message,
+            })?;
+        }
+        let config: GameConfig = value.try_into().map_err(|e: toml::de::Error| DataError {
+            file: file.to_string(),
+            line: None,
+            message: e.to_string(),
+        })?;
+        config.validate().map_err(|message| DataError {
+            file: file.to_string(),
+            line: None,
+            message,
+        })?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.window.width < 320.0 || self.window.height < 240.0 {
+            return Err("window must be at least 320x240".to_string());
+        }
+        if !LAYOUTS.contains(&self.controls.layout.as_str()) {
+            return Err(format!(
+                "unknown controls.layout '{}', expected one of {}",
+                self.controls.layout,
+                LAYOUTS.join(", ")
+            ));
+        }
+        if !(0.0..1.0).contains(&self.controls.stick_deadzone) {
+            return Err("controls.stick_deadzone must be at least 0 and below 1".to_string());
+        }
+        for (key, value) in [
+            ("master_volume", self.audio.master_volume),
+            ("sfx_volume", self.audio.sfx_volume),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("audio.{} must be between 0 and 1", key));
+            }
+        }
+        let gameplay = &self.gameplay;
+        if gameplay.lives == 0 {
+            return Err("gameplay.lives must be at least 1".to_string());
+        }
+        for (key, value) in [
+            ("player_speed", gameplay.player_speed),
+            ("player_acceleration", gameplay.player_acceleration),
+            ("player_deceleration", gameplay.player_deceleration),
+            ("boss_life_points", gameplay.boss_life_points),
+            ("tank_life_points", gameplay.tank_life_points),
+            ("max_life_points", gameplay.max_life_points),
+            ("invulnerability_time", gameplay.invulnerability_time),
+            ("pickup_interval", gameplay.pickup_interval),
+            ("asteroid_interval", gameplay.asteroid_interval),
+        ] {
+            if value <= 0.0 {
+                return Err(format!("gameplay.{} must be positive", key));
+            }
+        }
+        Ok(())
+    }
+}
+
+// `gameplay.player_speed=700` sets that key, the value is read as TOML and falls back to a string
+fn apply_override(config: &mut toml::Value, assignment: &str) -> Result<(), String> {
+    let (path, raw) = assignment
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, got '{}'", assignment))?;
+    let value = toml::from_str::<toml::Value>(&format!("value = {}", raw.trim()))
+        .ok()
+        .and_then(|table| table.get("value").cloned())
+        .unwrap_or_else(|| toml::Value::String(raw.trim().to_string()));
+    let mut keys = path.trim().split('.').peekable();
+    let mut table = config;
+    while let Some(key) = keys.next() {
+        let map = table
+            .as_table_mut()
+            .ok_or_else(|| format!("'{}' is not a section", path))?;
+        if keys.peek().is_none() {
+            map.insert(key.to_string(), value);
+            return Ok(());
+        }
+        table = map
+            .entry(key.to_string())
+            .or_insert_with(|| toml::Value::Table(Default::default()));
+    }
+    Err(format!("empty key in '{}'", assignment))
+}
@@ -0,0 +1,190 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ggez::input::keyboard::KeyCode;
+use serde::{Deserialize, Serialize};
+
+pub const KEY_BINDINGS_FILE: &str = "keybindings.toml";
+pub const LAYOUTS: [&str; 3] = ["arrows", "wasd", "zqsd"];
+
+// what the player wants to do, independent of the key that asked for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Fire,
+    // hands over the next weapon
+    SwitchWeapon,
+    Restart,
+    Pause,
+    Mute,
+    // picks the highlighted menu entry, Fire does the same
+    Confirm,
+}
+
+impl Action {
+    pub const ALL: [Action; 10] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Fire,
+        Action::SwitchWeapon,
+        Action::Restart,
+        Action::Pause,
+        Action::Mute,
+        Action::Confirm,
+    ];
+}
+
+// every action with the keys bound to it, key names as ggez spells them: "Left", "Space", "W", ...
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyBindings {
+    pub move_left: Vec<KeyCode>,
+    pub move_right: Vec<KeyCode>,
+    pub move_up: Vec<KeyCode>,
+    pub move_down: Vec<KeyCode>,
+    pub fire: Vec<KeyCode>,
+    pub restart: Vec<KeyCode>,
+    pub pause: Vec<KeyCode>,
+    // files saved before these actions existed get the default keys
+    #[serde(default = "default_mute_keys")]
+    pub mute: Vec<KeyCode>,
+    #[serde(default = "default_confirm_keys")]
+    pub confirm: Vec<KeyCode>,
+    #[serde(default = "default_switch_weapon_keys")]
+    pub switch_weapon: Vec<KeyCode>,
+}
+
+fn default_mute_keys() -> Vec<KeyCode> {
+    vec![KeyCode::M]
+}
+
+fn default_confirm_keys() -> Vec<KeyCode> {
+    vec![KeyCode::Return]
+}
+
+fn default_switch_weapon_keys() -> Vec<KeyCode> {
+    vec![KeyCode::E, KeyCode::Tab]
+}
+
+impl KeyBindings {
+    // the arrow keys always work, "wasd" and "zqsd" (AZERTY) add letters on top of them
+    pub fn preset(layout: &str) -> Option<KeyBindings> {
+        let (left, right, up, down) = match layout {
+            "arrows" => (vec![], vec![], vec![], vec![]),
+            "wasd" => (
+                vec![KeyCode::A],
+                vec![KeyCode::D],
+                vec![KeyCode::W],
+                vec![KeyCode::S],
+            ),
+            "zqsd" => (
+                vec![KeyCode::Q],
+                vec![KeyCode::D],
+                vec![KeyCode::Z],
+                vec![KeyCode::S],
+            ),
+            _ => return None,
+        };
+        let with_arrow = |mut keys: Vec<KeyCode>, arrow: KeyCode| {
+            keys.insert(0, arrow);
+            keys
+        };
+        Some(KeyBindings {
+            move_left: with_arrow(left, KeyCode::Left),
+            move_right: with_arrow(right, KeyCode::Right),
+            move_up: with_arrow(up, KeyCode::Up),
+            move_down: with_arrow(down, KeyCode::Down),
+            fire: vec![KeyCode::Space],
+            restart: vec![KeyCode::R],
+            pause: vec![KeyCode::Escape, KeyCode::P],
+            mute: default_mute_keys(),
+            confirm: default_confirm_keys(),
+            switch_weapon: default_switch_weapon_keys(),
+        })
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        match action {
+            Action::MoveLeft => &self.move_left,
+            Action::MoveRight => &self.move_right,
+            Action::MoveUp => &self.move_up,
+            Action::MoveDown => &self.move_down,
+            Action::Fire => &self.fire,
+            Action::Restart => &self.restart,
+            Action::Pause => &self.pause,
+            Action::Mute => &self.mute,
+            Action::Confirm => &self.confirm,
+            Action::SwitchWeapon => &self.switch_weapon,
+        }
+    }
+
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|&action| self.keys(action).contains(&key))
+    }
+
+    pub fn from_toml(text: &str) -> Result<KeyBindings, String> {
+        let bindings: KeyBindings = toml::from_str(text).map_err(|e| e.to_string())?;
+        for (index, &first) in Action::ALL.iter().enumerate() {
+            for &key in bindings.keys(first) {
+                if let Some(&second) = Action::ALL[index + 1..]
+                    .iter()
+                    .find(|&&other| bindings.keys(other).contains(&key))
+                {
+                    return Err(format!(
+                        "{:?} is bound to both {:?} and {:?}",
+                        key, first, second
+                    ));
+                }
+            }
+        }
+        Ok(bindings)
+    }
+
+    // a missing file is created from the layout preset so players have something to edit,
+    // a broken one is reported and left alone
+    pub fn load(path: &Path, layout: &str) -> KeyBindings {
+        let preset = KeyBindings::preset(layout).expect("layout is checked with the config");
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                if let Err(e) = preset.save(path) {
+                    eprintln!("could not save key bindings to {}: {}", path.display(), e);
+                }
+                return preset;
+            }
+            Err(e) => {
+                eprintln!("could not read {}: {}", path.display(), e);
+                return preset;
+            }
+        };
+        match KeyBindings::from_toml(&text) {
+            Ok(bindings) => bindings,
+            Err(e) => {
+                eprintln!(
+                    "{} is invalid ({}), using the {} layout",
+                    path.display(),
+                    e,
+                    layout
+                );
+                preset
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let text = toml::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, text)
+    }
+}
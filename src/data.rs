@@ -0,0 +1,123 @@
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+
+use crate::config::GameConfig;
+use crate::enemies::{EnemyCatalog, ENEMIES_FILE};
+use crate::waves::{parse_waves, Wave, WAVES_FILE};
+use crate::weapons::{WeaponCatalog, WEAPONS_FILE};
+
+// a problem in one of the data files, `line` is 1-based when known
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataError {
+    pub file: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} line {}: {}", self.file, line, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+impl From<DataError> for ggez::GameError {
+    fn from(error: DataError) -> Self {
+        ggez::GameError::ResourceLoadError(error.to_string())
+    }
+}
+
+// everything designers can change in resources/ without recompiling
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameData {
+    pub enemies: EnemyCatalog,
+    pub waves: Vec<Wave>,
+    pub weapons: WeaponCatalog,
+    pub config: GameConfig,
+}
+
+impl Default for GameData {
+    // the copies shipped with the game, used by tests
+    fn default() -> Self {
+        GameData::from_toml(
+            include_str!("../resources/enemies.toml"),
+            include_str!("../resources/waves.toml"),
+            include_str!("../resources/weapons.toml"),
+        )
+        .unwrap()
+    }
+}
+
+impl GameData {
+    pub fn from_toml(enemies: &str, waves: &str, weapons: &str) -> Result<GameData, DataError> {
+        let enemies = EnemyCatalog::from_toml(enemies)?;
+        let waves = parse_waves(waves, &enemies)?;
+        let weapons = WeaponCatalog::from_toml(weapons)?;
+        Ok(GameData {
+            enemies,
+            waves,
+            weapons,
+            config: GameConfig::default(),
+        })
+    }
+
+    // every data file through `read`, which gets the resource path ("/enemies.toml", ...)
+    pub fn load(
+        mut read: impl FnMut(&str) -> Result<String, String>,
+    ) -> Result<GameData, DataError> {
+        let mut text = |file: &str| {
+            read(file).map_err(|message| DataError {
+                file: file.to_string(),
+                line: None,
+                message,
+            })
+        };
+        let enemies = text(ENEMIES_FILE)?;
+        let waves = text(WAVES_FILE)?;
+        let weapons = text(WEAPONS_FILE)?;
+        GameData::from_toml(&enemies, &waves, &weapons)
+    }
+
+    pub fn with_config(self, config: GameConfig) -> GameData {
+        GameData { config, ..self }
+    }
+}
+
+pub fn parse_toml<T: DeserializeOwned>(file: &str, text: &str) -> Result<T, DataError> {
+    toml::from_str(text).map_err(|e| DataError {
+        file: file.to_string(),
+        line: e.line_col().map(|(line, _)| line + 1),
+        message: e.to_string(),
+    })
+}
+
+// line of the `index`-th `[[header]]` table, used to point validation errors at an entry
+pub fn line_of_entry(text: &str, header: &str, index: usize) -> Option<usize> {
+    let table = format!("[[{}]]", header);
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| line.trim() == table)
+        .nth(index)
+        .map(|(number, _)| number + 1)
+}
+
+// line of `key = ...` inside that entry, falls back to the entry itself
+pub fn line_of_field(text: &str, header: &str, index: usize, key: &str) -> Option<usize> {
+    let start = line_of_entry(text, header, index)?;
+    text.lines()
+        .enumerate()
+        .skip(start)
+        .take_while(|(_, line)| !line.trim_start().starts_with('['))
+        .find(|(_, line)| {
+            line.split('=')
+                .next()
+                .is_some_and(|name| name.trim() == key)
+        })
+        .map(|(number, _)| number + 1)
+        .or(Some(start))
+}
@@ -0,0 +1,191 @@
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::actors::Subtype;
+use crate::data::{line_of_field, parse_toml, DataError};
+
+pub const ENEMIES_FILE: &str = "/enemies.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BehaviourKind {
+    Basic,
+    Kamikaze,
+}
+
+impl BehaviourKind {
+    fn from_name(name: &str) -> Option<BehaviourKind> {
+        match name {
+            "basic" => Some(BehaviourKind::Basic),
+            "kamikaze" => Some(BehaviourKind::Kamikaze),
+            _ => None,
+        }
+    }
+
+    pub fn subtype(self) -> Subtype {
+        match self {
+            BehaviourKind::Basic => Subtype::BasicEnemy,
+            BehaviourKind::Kamikaze => Subtype::KamikazeEnemy,
+        }
+    }
+}
+
+// how a kamikaze flies: top speed in pixels per second, turning in radians per second and how
+// quickly it gets up to speed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Steering {
+    pub max_speed: f32,
+    pub turn_rate: f32,
+    pub acceleration: f32,
+}
+
+impl Default for Steering {
+    fn default() -> Self {
+        Steering {
+            max_speed: 380.0,
+            turn_rate: 2.2,
+            acceleration: 500.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnemyArchetype {
+    pub name: String,
+    pub sprite: String,
+    pub hitbox: f32,
+    pub base_hp: f32,
+    pub hp_per_score: f32,
+    pub speed: f32,
+    pub behaviour: BehaviourKind,
+    pub weight: u32,
+    pub steering: Steering,
+}
+
+// the behaviour is kept as text so a typo can be reported on its own line
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawArchetype {
+    name: String,
+    sprite: String,
+    hitbox: f32,
+    base_hp: f32,
+    hp_per_score: f32,
+    speed: f32,
+    behaviour: String,
+    weight: u32,
+    // kamikaze steering, the defaults fill in whatever is left out
+    max_speed: Option<f32>,
+    turn_rate: Option<f32>,
+    acceleration: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EnemyFile {
+    enemy: Vec<RawArchetype>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnemyCatalog {
+    pub archetypes: Vec<EnemyArchetype>,
+}
+
+impl EnemyCatalog {
+    pub fn from_toml(text: &str) -> Result<EnemyCatalog, DataError> {
+        let file: EnemyFile = parse_toml(ENEMIES_FILE, text)?;
+        let error = |index: usize, key: &str, message: String| DataError {
+            file: ENEMIES_FILE.to_string(),
+            line: line_of_field(text, "enemy", index, key),
+            message,
+        };
+        if file.enemy.is_empty() {
+            return Err(DataError {
+                file: ENEMIES_FILE.to_string(),
+                line: None,
+                message: "at least one enemy is needed".to_string(),
+            });
+        }
+        let mut archetypes: Vec<EnemyArchetype> = Vec::new();
+        for (index, enemy) in file.enemy.into_iter().enumerate() {
+            if enemy.name.is_empty() {
+                return Err(error(index, "name", "name can't be empty".to_string()));
+            }
+            if archetypes.iter().any(|e| e.name == enemy.name) {
+                let message = format!("duplicate enemy '{}'", enemy.name);
+                return Err(error(index, "name", message));
+            }
+            if !enemy.sprite.starts_with('/') {
+                let message = "sprite must start with '/'".to_string();
+                return Err(error(index, "sprite", message));
+            }
+            for (key, value) in [
+                ("hitbox", enemy.hitbox),
+                ("base_hp", enemy.base_hp),
+                ("speed", enemy.speed),
+            ] {
+                if value <= 0.0 {
+                    return Err(error(index, key, format!("{} must be positive", key)));
+                }
+            }
+            let defaults = Steering::default();
+            let steering = Steering {
+                max_speed: enemy.max_speed.unwrap_or(defaults.max_speed),
+                turn_rate: enemy.turn_rate.unwrap_or(defaults.turn_rate),
+                acceleration: enemy.acceleration.unwrap_or(defaults.acceleration),
+            };
+            for (key, value) in [
+                ("max_speed", steering.max_speed),
+                ("turn_rate", steering.turn_rate),
+                ("acceleration", steering.acceleration),
+            ] {
+                if value <= 0.0 {
+                    return Err(error(index, key, format!("{} must be positive", key)));
+                }
+            }
+            if enemy.hp_per_score < 0.0 {
+                let message = "hp_per_score can't be negative".to_string();
+                return Err(error(index, "hp_per_score", message));
+            }
+            if enemy.weight == 0 {
+                let message = "weight must be at least 1".to_string();
+                return Err(error(index, "weight", message));
+            }
+            let Some(behaviour) = BehaviourKind::from_name(&enemy.behaviour) else {
+                let message = format!(
+                    "unknown behaviour '{}', expected \"basic\" or \"kamikaze\"",
+                    enemy.behaviour
+                );
+                return Err(error(index, "behaviour", message));
+            };
+            archetypes.push(EnemyArchetype {
+                name: enemy.name,
+                sprite: enemy.sprite,
+                hitbox: enemy.hitbox,
+                base_hp: enemy.base_hp,
+                hp_per_score: enemy.hp_per_score,
+                speed: enemy.speed,
+                behaviour,
+                weight: enemy.weight,
+                steering,
+            });
+        }
+        Ok(EnemyCatalog { archetypes })
+    }
+
+    pub fn get(&self, index: usize) -> &EnemyArchetype {
+        &self.archetypes[index]
+    }
+
+    // weighted random archetype index
+    pub fn pick(&self, rng: &mut impl Rng) -> usize {
+        let total: u32 = self.archetypes.iter().map(|a| a.weight).sum();
+        let mut roll = rng.gen_range(0..total);
+        for (index, archetype) in self.archetypes.iter().enumerate() {
+            if roll < archetype.weight {
+                return index;
+            }
+            roll -= archetype.weight;
+        }
+        unreachable!()
+    }
+}
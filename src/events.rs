@@ -0,0 +1,68 @@
+use ggez::glam::Vec2;
+
+use crate::actors::Subtype;
+
+// everything noteworthy that happened during one simulation step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameEvent {
+    ShotFired {
+        subtype: Subtype,
+        position: Vec2,
+    },
+    ShotHitEnemy {
+        shot: Subtype,
+        enemy: Subtype,
+        position: Vec2,
+    },
+    EnemyKilled {
+        subtype: Subtype,
+        position: Vec2,
+    },
+    BossDefeated {
+        subtype: Subtype,
+        position: Vec2,
+    },
+    PickupCollected {
+        subtype: Subtype,
+        position: Vec2,
+    },
+    PlayerDamaged {
+        position: Vec2,
+    },
+    PlayerKilled {
+        position: Vec2,
+    },
+    WaveStarted {
+        number: u32,
+    },
+    BossSpawned {
+        subtype: Subtype,
+        position: Vec2,
+    },
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Statistics {
+    pub shots_fired: u32,
+    pub shots_hit: u32,
+    pub enemies_killed: u32,
+    pub bosses_defeated: u32,
+    pub pickups_collected: u32,
+    pub damage_taken: u32,
+    pub waves_reached: u32,
+}
+
+impl Statistics {
+    pub fn record(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::ShotFired { .. } => self.shots_fired += 1,
+            GameEvent::ShotHitEnemy { .. } => self.shots_hit += 1,
+            GameEvent::EnemyKilled { .. } => self.enemies_killed += 1,
+            GameEvent::BossDefeated { .. } => self.bosses_defeated += 1,
+            GameEvent::PickupCollected { .. } => self.pickups_collected += 1,
+            GameEvent::PlayerDamaged { .. } => self.damage_taken += 1,
+            GameEvent::WaveStarted { number } => self.waves_reached = *number,
+            GameEvent::PlayerKilled { .. } | GameEvent::BossSpawned { .. } => (),
+        }
+    }
+}
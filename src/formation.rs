@@ -0,0 +1,126 @@
+use ggez::glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::{create_enemy, Actor, Subtype};
+use crate::enemies::EnemyCatalog;
+use crate::screen_size;
+
+pub const FORMATION_ROWS: usize = 5;
+pub const FORMATION_COLUMNS: usize = 11;
+const SPACING: Vec2 = Vec2::new(70.0, 60.0);
+// sideways per step and down at an edge, in pixels
+const STEP: f32 = 15.0;
+const DROP: f32 = 30.0;
+// seconds between two steps with the whole formation alive and with one alien left
+const SLOWEST_STEP: f32 = 0.6;
+const FASTEST_STEP: f32 = 0.05;
+const EDGE_MARGIN: f32 = 40.0;
+// seconds between two shots of the front row
+pub const INVADER_FIRE_INTERVAL: f32 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameMode {
+    // endless waves, kamikazes and bosses
+    #[default]
+    Survival,
+    // a marching grid of aliens, a new one every time it's wiped out
+    Formation,
+}
+
+pub fn is_invader(actor: &Actor) -> bool {
+    actor.subtag == Subtype::InvaderEnemy
+}
+
+// the aliens themselves are ordinary enemies, this only keeps the march going
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formation {
+    // formations cleared so far, every new one starts a row lower
+    pub level: u32,
+    total: usize,
+    // +1 marching right, -1 marching left
+    direction: f32,
+    step_timer: f32,
+}
+
+impl Formation {
+    pub fn new() -> Formation {
+        Formation {
+            level: 0,
+            total: FORMATION_ROWS * FORMATION_COLUMNS,
+            direction: 1.0,
+            step_timer: 0.0,
+        }
+    }
+
+    // rows x columns of the first archetype, centred at the top of the screen
+    pub fn spawn(&mut self, catalog: &EnemyCatalog, current_score: f32) -> Vec<Actor> {
+        let width = (FORMATION_COLUMNS - 1) as f32 * SPACING.x;
+        let top = 100.0 + (self.level.min(4) as f32) * DROP;
+        let left = (screen_size().x - width) / 2.0;
+        self.level += 1;
+        self.direction = 1.0;
+        self.step_timer = 0.0;
+        let mut invaders = Vec::new();
+        for row in 0..FORMATION_ROWS {
+            for column in 0..FORMATION_COLUMNS {
+                let position = Vec2::new(
+                    left + column as f32 * SPACING.x,
+                    top + row as f32 * SPACING.y,
+                );
+                let mut invader = create_enemy(catalog, 0, position, position, 0.0, current_score);
+                invader.subtag = Subtype::InvaderEnemy;
+                invaders.push(invader);
+            }
+        }
+        self.total = invaders.len();
+        invaders
+    }
+
+    // fewer aliens march faster, the last one almost every frame
+    pub fn step_interval(&self, alive: usize) -> f32 {
+        let share = alive as f32 / self.total.max(1) as f32;
+        FASTEST_STEP + (SLOWEST_STEP - FASTEST_STEP) * share
+    }
+
+    // moves every invader one step sideways, or down and around when one of them reached an edge
+    pub fn update(&mut self, enemies: &mut [Actor], dt: f32) {
+        let alive = enemies.iter().filter(|enemy| is_invader(enemy)).count();
+        self.step_timer += dt;
+        if alive == 0 || self.step_timer < self.step_interval(alive) {
+            return;
+        }
+        self.step_timer = 0.0;
+        let at_edge = enemies
+            .iter()
+            .filter(|enemy| is_invader(enemy))
+            .any(|invader| {
+                let next = invader.position.x + self.direction * STEP;
+                next < EDGE_MARGIN || next > screen_size().x - EDGE_MARGIN
+            });
+        let offset = if at_edge {
+            self.direction = -self.direction;
+            Vec2::new(0.0, DROP)
+        } else {
+            Vec2::new(self.direction * STEP, 0.0)
+        };
+        for invader in enemies.iter_mut().filter(|enemy| is_invader(enemy)) {
+            invader.position += offset;
+            invader.desired_pos = invader.position;
+        }
+    }
+}
+
+// indices of the invaders nothing else stands below, only those may fire
+pub fn front_row(enemies: &[Actor]) -> Vec<usize> {
+    let blocked = |invader: &Actor| {
+        enemies.iter().any(|other| {
+            is_invader(other)
+                && (other.position.x - invader.position.x).abs() < SPACING.x / 2.0
+                && other.position.y > invader.position.y
+        })
+    };
+    (0..enemies.len())
+        .filter(|&index| is_invader(&enemies[index]) && !blocked(&enemies[index]))
+        .collect()
+}
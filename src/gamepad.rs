@@ -0,0 +1,107 @@
+use ggez::event::{Axis, Button};
+use ggez::glam::Vec2;
+
+use crate::controls::Action;
+
+// the fixed button layout, named after an Xbox pad: A or the right trigger fires,
+// Start pauses, Back restarts and the d-pad moves like the keyboard does
+pub fn button_action(button: Button) -> Option<Action> {
+    match button {
+        Button::South | Button::RightTrigger2 => Some(Action::Fire),
+        Button::North => Some(Action::SwitchWeapon),
+        Button::Start => Some(Action::Pause),
+        Button::Select => Some(Action::Restart),
+        Button::DPadLeft => Some(Action::MoveLeft),
+        Button::DPadRight => Some(Action::MoveRight),
+        Button::DPadUp => Some(Action::MoveUp),
+        Button::DPadDown => Some(Action::MoveDown),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+// state of one pad, fed by the ggez gamepad events so it can also be fed by hand
+pub struct GamepadInput {
+    deadzone: f32,
+    // left stick as reported, y already pointing down like the screen
+    raw_stick: Vec2,
+    // right stick the same way, used for aiming
+    raw_aim: Vec2,
+    // actions held through buttons, released together if the pad goes away
+    pressed: Vec<Action>,
+}
+
+impl GamepadInput {
+    pub fn new(deadzone: f32) -> GamepadInput {
+        GamepadInput {
+            deadzone,
+            raw_stick: Vec2::ZERO,
+            raw_aim: Vec2::ZERO,
+            pressed: Vec::new(),
+        }
+    }
+
+    // returns the stick whose direction after the deadzone changed
+    pub fn axis(&mut self, axis: Axis, value: f32) -> Option<Stick> {
+        let (stick, aim) = (self.stick(), self.aim());
+        match axis {
+            Axis::LeftStickX => self.raw_stick.x = value,
+            Axis::LeftStickY => self.raw_stick.y = -value,
+            Axis::RightStickX => self.raw_aim.x = value,
+            Axis::RightStickY => self.raw_aim.y = -value,
+            _ => return None,
+        }
+        if self.stick() != stick {
+            Some(Stick::Left)
+        } else if self.aim() != aim {
+            Some(Stick::Right)
+        } else {
+            None
+        }
+    }
+
+    // radial deadzone, the rest of the range is stretched so small tilts still start slow
+    fn apply_deadzone(&self, raw: Vec2) -> Vec2 {
+        let length = raw.length();
+        if length <= self.deadzone {
+            return Vec2::ZERO;
+        }
+        let strength = ((length - self.deadzone) / (1.0 - self.deadzone)).min(1.0);
+        raw / length * strength
+    }
+
+    pub fn stick(&self) -> Vec2 {
+        self.apply_deadzone(self.raw_stick)
+    }
+
+    // unit direction of the right stick, None while it rests in the deadzone
+    pub fn aim(&self) -> Option<Vec2> {
+        self.apply_deadzone(self.raw_aim).try_normalize()
+    }
+
+    pub fn button_down(&mut self, button: Button) -> Option<Action> {
+        let action = button_action(button)?;
+        if !self.pressed.contains(&action) {
+            self.pressed.push(action);
+        }
+        Some(action)
+    }
+
+    pub fn button_up(&mut self, button: Button) -> Option<Action> {
+        let action = button_action(button)?;
+        self.pressed.retain(|&pressed| pressed != action);
+        Some(action)
+    }
+
+    // forgets everything the pad was doing, returns the actions that have to be released
+    pub fn disconnect(&mut self) -> Vec<Action> {
+        self.raw_stick = Vec2::ZERO;
+        self.raw_aim = Vec2::ZERO;
+        std::mem::take(&mut self.pressed)
+    }
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use ggez::glam::Vec2;
+
+use crate::actors::Actor;
+
+// uniform grid broad phase, every actor is bucketed into each cell its hit circle overlaps
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> SpatialGrid {
+        SpatialGrid {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for bucket in self.cells.values_mut() {
+            bucket.clear();
+        }
+    }
+
+    pub fn rebuild(&mut self, actors: &[Actor]) {
+        self.clear();
+        for (index, actor) in actors.iter().enumerate() {
+            self.insert(index, actor.position, actor.box_size);
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> (i32, i32) {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn cells_around(&self, position: Vec2, radius: f32) -> impl Iterator<Item = (i32, i32)> {
+        let min = self.cell(position - radius);
+        let max = self.cell(position + radius);
+        (min.0..=max.0).flat_map(move |x| (min.1..=max.1).map(move |y| (x, y)))
+    }
+
+    pub fn insert(&mut self, index: usize, position: Vec2, radius: f32) {
+        for cell in self.cells_around(position, radius).collect::<Vec<_>>() {
+            self.cells.entry(cell).or_default().push(index);
+        }
+    }
+
+    // indices of everything that might touch the given circle, each reported once
+    pub fn query(&self, position: Vec2, radius: f32, found: &mut Vec<usize>) {
+        found.clear();
+        for cell in self.cells_around(position, radius) {
+            if let Some(bucket) = self.cells.get(&cell) {
+                found.extend_from_slice(bucket);
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+    }
+}
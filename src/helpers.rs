@@ -2,7 +2,7 @@ use ggez::glam::Vec2;
 use rand::Rng;
 
 use crate::actors::{Actor, TypeActor};
-use crate::SCREEN_SIZE;
+use crate::screen_size;
 
 pub fn smoothstep(t: f32) -> f32 {
     t * t * (3.0 - 2.0 * t)
@@ -13,45 +13,71 @@ pub fn position_to_direction(current_position: Vec2, desired_pos: Vec2) -> Vec2
     let dy = desired_pos.y - current_position.y;
     let dist_sq = dx * dx + dy * dy;
     let distance = dist_sq.sqrt();
+    // already there, no direction to go
+    if distance == 0.0 {
+        return Vec2::ZERO;
+    }
     Vec2::new(dx / distance, dy / distance)
 }
-pub fn make_rand_pos() -> Vec2 {
-    let mut rng = rand::thread_rng();
-    let x = rng.gen_range(5.0..SCREEN_SIZE.x);
-    let y = rng.gen_range(5.0..SCREEN_SIZE.y / 2.0);
+pub fn make_rand_pos(rng: &mut impl Rng) -> Vec2 {
+    let x = rng.gen_range(5.0..screen_size().x);
+    let y = rng.gen_range(5.0..screen_size().y / 2.0);
     Vec2::new(x, y)
 }
 
 pub fn clamp_player(player: &mut Actor) {
-    let screen_width = SCREEN_SIZE.x - 4.0;
-    let screen_height = SCREEN_SIZE.y - 4.0;
+    let screen_width = screen_size().x - 4.0;
+    let screen_height = screen_size().y - 4.0;
     if let TypeActor::Player = player.tag {
         player.position.x = player.position.x.clamp(0.0, screen_width);
         player.position.y = player.position.y.clamp(0.0, screen_height);
     }
 }
 
-pub fn random_offscreen_position(screen_width: f32, screen_height: f32) -> Vec2 {
-    let mut rng = rand::thread_rng();
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
 
-    let edge = rng.gen_range(0..4);
+pub fn random_offscreen_position(
+    rng: &mut impl Rng,
+    screen_width: f32,
+    screen_height: f32,
+) -> Vec2 {
+    let edge = match rng.gen_range(0..4) {
+        0 => Edge::Left,
+        1 => Edge::Right,
+        2 => Edge::Top,
+        _ => Edge::Bottom,
+    };
+    offscreen_position_on(rng, edge, screen_width, screen_height)
+}
 
+pub fn offscreen_position_on(
+    rng: &mut impl Rng,
+    edge: Edge,
+    screen_width: f32,
+    screen_height: f32,
+) -> Vec2 {
     match edge {
-        0 => Vec2 {
+        Edge::Left => Vec2 {
             x: -50.0,
             y: rng.gen_range(0.0..screen_height),
-        }, // Left
-        1 => Vec2 {
+        },
+        Edge::Right => Vec2 {
             x: screen_width + 50.0,
             y: rng.gen_range(0.0..screen_height),
-        }, // Right
-        2 => Vec2 {
+        },
+        Edge::Top => Vec2 {
             x: rng.gen_range(0.0..screen_width),
             y: -50.0,
-        }, // Top
-        _ => Vec2 {
+        },
+        Edge::Bottom => Vec2 {
             x: rng.gen_range(0.0..screen_width),
             y: screen_height + 50.0,
-        }, // Bottom
+        },
     }
 }
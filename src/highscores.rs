@@ -0,0 +1,156 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+pub const HIGH_SCORES_FILE: &str = "highscores.json";
+const HIGH_SCORES_VERSION: u32 = 1;
+pub const MAX_HIGH_SCORES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: f32,
+    // seconds since the unix epoch
+    pub date: u64,
+    pub seed: u64,
+    // length of the run in seconds
+    pub duration: f32,
+}
+
+impl HighScoreEntry {
+    pub fn new(name: &str, score: f32, seed: u64, duration: f32) -> HighScoreEntry {
+        let date = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or(0);
+        HighScoreEntry {
+            name: name.to_string(),
+            score,
+            date,
+            seed,
+            duration,
+        }
+    }
+
+    // YYYY-MM-DD in UTC
+    pub fn date_string(&self) -> String {
+        let (year, month, day) = civil_from_days((self.date / 86_400) as i64);
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+// days since 1970-01-01 to a calendar date, from Howard Hinnant's date algorithms
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    pub version: u32,
+    pub entries: Vec<HighScoreEntry>,
+}
+
+impl Default for HighScoreTable {
+    fn default() -> Self {
+        HighScoreTable {
+            version: HIGH_SCORES_VERSION,
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl HighScoreTable {
+    // a missing file is an empty table, a broken one is moved aside so it isn't overwritten
+    pub fn load(path: &Path) -> HighScoreTable {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return HighScoreTable::default(),
+            Err(e) => {
+                eprintln!("could not read {}: {}", path.display(), e);
+                return HighScoreTable::default();
+            }
+        };
+        match serde_json::from_str::<HighScoreTable>(&text) {
+            Ok(table) if table.version == HIGH_SCORES_VERSION => table.sorted(),
+            Ok(table) => {
+                eprintln!(
+                    "{} has unsupported version {}, starting a new table",
+                    path.display(),
+                    table.version
+                );
+                HighScoreTable::set_aside(path);
+                HighScoreTable::default()
+            }
+            Err(e) => {
+                eprintln!(
+                    "{} is corrupted ({}), starting a new table",
+                    path.display(),
+                    e
+                );
+                HighScoreTable::set_aside(path);
+                HighScoreTable::default()
+            }
+        }
+    }
+
+    fn set_aside(path: &Path) {
+        let backup = path.with_extension("json.bak");
+        if let Err(e) = fs::rename(path, &backup) {
+            eprintln!("could not move {} aside: {}", path.display(), e);
+        }
+    }
+
+    // written to a temporary file first, so a crash never leaves half a table behind
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temporary, path)
+    }
+
+    fn sorted(mut self) -> HighScoreTable {
+        self.entries.sort_by(|a, b| b.score.total_cmp(&a.score));
+        self.entries.truncate(MAX_HIGH_SCORES);
+        self
+    }
+
+    pub fn qualifies(&self, score: f32) -> bool {
+        self.entries.len() < MAX_HIGH_SCORES
+            || self.entries.last().is_some_and(|last| score > last.score)
+    }
+
+    // keeps the table in rank order, returns the 0-based rank if the run made it in
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        if !self.qualifies(entry.score) {
+            return None;
+        }
+        // ties go below the runs that got there first
+        let rank = self
+            .entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(rank, entry);
+        self.entries.truncate(MAX_HIGH_SCORES);
+        Some(rank)
+    }
+}
@@ -4,9 +4,27 @@ mod actors;
 mod animation;
 mod assets;
 mod behaviour;
+mod bunkers;
+mod config;
+mod controls;
+mod data;
+mod enemies;
+mod events;
+mod formation;
+mod gamepad;
+mod grid;
 mod helpers;
+mod highscores;
+mod patterns;
+mod replay;
+mod scenes;
+mod simulation;
+mod sound;
 mod state;
-use std::path;
+mod waves;
+mod weapons;
+use std::path::{self, PathBuf};
+use std::sync::OnceLock;
 
 #[cfg(test)]
 mod tests;
@@ -16,15 +34,131 @@ use ggez::event;
 use ggez::glam::Vec2;
 use ggez::ContextBuilder;
 
-pub const SCREEN_SIZE: Vec2 = Vec2::new(1200.0, 1000.0);
+// fixed once the config is loaded, everything before that (and the tests) sees the default window
+static SCREEN_SIZE: OnceLock<Vec2> = OnceLock::new();
+
+pub fn screen_size() -> Vec2 {
+    *SCREEN_SIZE.get_or_init(|| {
+        let window = config::WindowConfig::default();
+        Vec2::new(window.width, window.height)
+    })
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct LaunchOptions {
+    pub seed: Option<u64>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub headless: bool,
+    pub name: Option<String>,
+    pub config: Option<PathBuf>,
+    // `section.key=value` pairs applied on top of the config file
+    pub overrides: Vec<String>,
+}
+
+impl LaunchOptions {
+    // name for the high score table, falls back to the account name
+    pub fn player_name(&self) -> String {
+        self.name
+            .clone()
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .unwrap_or_else(|| "Player".to_string())
+    }
+}
+
+// reads `--seed <number>`, `--record <file>`, `--replay <file>`, `--headless`, `--name <name>`,
+// `--config <file>` and `--set <section.key=value>`
+fn parse_options(mut args: impl Iterator<Item = String>) -> LaunchOptions {
+    let mut options = LaunchOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                let value = args.next().unwrap_or_default();
+                match value.parse() {
+                    Ok(seed) => options.seed = Some(seed),
+                    Err(_) => eprintln!("invalid seed '{}', using a random one", value),
+                }
+            }
+            "--record" => options.record = args.next().map(PathBuf::from),
+            "--replay" => options.replay = args.next().map(PathBuf::from),
+            "--headless" => options.headless = true,
+            "--name" => options.name = args.next(),
+            "--config" => options.config = args.next().map(PathBuf::from),
+            "--set" => options.overrides.extend(args.next()),
+            _ => eprintln!("unknown argument '{}'", arg),
+        }
+    }
+    options
+}
+
+// next to the sources when started through cargo, next to the executable when shipped,
+// otherwise in the working directory
+fn resources_dir() -> PathBuf {
+    let from_cargo = std::env::var_os("CARGO_MANIFEST_DIR").map(PathBuf::from);
+    let from_exe = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(path::Path::to_path_buf));
+    from_cargo
+        .into_iter()
+        .chain(from_exe)
+        .map(|dir| dir.join("resources"))
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(|| PathBuf::from("./resources"))
+}
+
 fn main() {
+    let options = parse_options(std::env::args().skip(1));
+    let config_path = options
+        .config
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(config::CONFIG_FILE));
+    let config = match config::GameConfig::load(&config_path, &options.overrides) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    let window = &config.window;
+    SCREEN_SIZE
+        .set(Vec2::new(window.width, window.height))
+        .expect("screen size set twice");
+    // `--headless --replay <file>` plays the replay without a window and prints how it ended
+    if let (true, Some(path)) = (options.headless, &options.replay) {
+        let replay = match replay::Replay::load(path) {
+            Ok(replay) => replay,
+            Err(e) => {
+                eprintln!("could not load replay {}: {}", path.display(), e);
+                return;
+            }
+        };
+        let resources = resources_dir();
+        let data = match data::GameData::load(|file| {
+            std::fs::read_to_string(resources.join(file.trim_start_matches('/')))
+                .map_err(|e| e.to_string())
+        }) {
+            Ok(data) => data.with_config(config),
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+        let simulation = replay::run_replay(&replay, data);
+        println!(
+            "frames: {}, score: {:.2}, game over: {}",
+            simulation.frame, simulation.current_score, simulation.game_over
+        );
+        return;
+    }
     let c = conf::Conf::new()
-        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.x, SCREEN_SIZE.y));
+        .window_mode(conf::WindowMode::default().dimensions(window.width, window.height));
     let (mut ctx, event_loop) = ContextBuilder::new("spaceship", "Pesho153Python")
         .default_conf(c)
-        .add_resource_path(path::PathBuf::from("./resources"))
+        .window_setup(conf::WindowSetup::default().vsync(window.vsync))
+        .add_resource_path(resources_dir())
         .build()
         .unwrap();
-    let state = state::State::new(&mut ctx).unwrap();
+    let state = state::State::new(&mut ctx, options, config).unwrap();
     event::run(ctx, event_loop, state);
 }
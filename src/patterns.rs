@@ -0,0 +1,169 @@
+use std::f32::consts::TAU;
+
+use ggez::glam::Vec2;
+
+use crate::actors::{create_pattern_shot, Actor, Subtype};
+use crate::helpers::position_to_direction;
+
+// a volley of enemy bullets, angles are in radians and speeds in pixels per second
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    // evenly around the full circle, the first bullet towards the target
+    Radial {
+        count: usize,
+        speed: f32,
+    },
+    // spread over `spread` radians, centred on the target
+    AimedFan {
+        count: usize,
+        spread: f32,
+        speed: f32,
+    },
+    // one bullet every `interval` seconds from an emitter turning at `angular_velocity`
+    Spiral {
+        count: usize,
+        speed: f32,
+        angular_velocity: f32,
+        interval: f32,
+    },
+    // one bullet every `interval` seconds towards the target, each weaving from side to side
+    SineStream {
+        count: usize,
+        speed: f32,
+        interval: f32,
+        amplitude: f32,
+        frequency: f32,
+    },
+    // any other pattern whose bullets hang where they were fired for `delay` seconds first
+    Delayed {
+        delay: f32,
+        pattern: Box<Pattern>,
+    },
+}
+
+// where a bullet is at any age follows from how it was fired, so volleys can be checked without
+// running the simulation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bullet {
+    pub origin: Vec2,
+    pub direction: Vec2,
+    pub speed: f32,
+    // seconds spent at the origin before moving
+    pub delay: f32,
+    // sideways weave, in pixels and waves per second
+    pub amplitude: f32,
+    pub frequency: f32,
+    pub age: f32,
+}
+
+impl Bullet {
+    pub fn new(origin: Vec2, direction: Vec2, speed: f32) -> Bullet {
+        Bullet {
+            origin,
+            direction,
+            speed,
+            delay: 0.0,
+            amplitude: 0.0,
+            frequency: 0.0,
+            age: 0.0,
+        }
+    }
+
+    pub fn position_at(&self, age: f32) -> Vec2 {
+        let t = (age - self.delay).max(0.0);
+        let weave = self.amplitude * (TAU * self.frequency * t).sin();
+        self.origin + self.direction * self.speed * t + self.direction.perp() * weave
+    }
+
+    // ages the bullet and returns its new position
+    pub fn advance(&mut self, dt: f32) -> Vec2 {
+        self.age += dt;
+        self.position_at(self.age)
+    }
+}
+
+// `count` directions over `spread` radians centred on `aim`
+fn fan(aim: Vec2, count: usize, spread: f32) -> Vec<Vec2> {
+    if count == 1 {
+        return vec![aim];
+    }
+    (0..count)
+        .map(|i| {
+            let offset = spread * (i as f32 / (count - 1) as f32 - 0.5);
+            Vec2::from_angle(offset).rotate(aim)
+        })
+        .collect()
+}
+
+impl Pattern {
+    pub fn emit(&self, origin: Vec2, target: Vec2) -> Vec<Bullet> {
+        let mut aim = position_to_direction(origin, target);
+        // nothing to aim at, straight down
+        if aim == Vec2::ZERO {
+            aim = Vec2::Y;
+        }
+        match self {
+            Pattern::Radial { count, speed } => {
+                let step = TAU / *count as f32;
+                (0..*count)
+                    .map(|i| {
+                        let direction = Vec2::from_angle(step * i as f32).rotate(aim);
+                        Bullet::new(origin, direction, *speed)
+                    })
+                    .collect()
+            }
+            Pattern::AimedFan {
+                count,
+                spread,
+                speed,
+            } => fan(aim, *count, *spread)
+                .into_iter()
+                .map(|direction| Bullet::new(origin, direction, *speed))
+                .collect(),
+            Pattern::Spiral {
+                count,
+                speed,
+                angular_velocity,
+                interval,
+            } => (0..*count)
+                .map(|i| {
+                    let delay = interval * i as f32;
+                    let direction = Vec2::from_angle(angular_velocity * delay).rotate(aim);
+                    Bullet {
+                        delay,
+                        ..Bullet::new(origin, direction, *speed)
+                    }
+                })
+                .collect(),
+            Pattern::SineStream {
+                count,
+                speed,
+                interval,
+                amplitude,
+                frequency,
+            } => (0..*count)
+                .map(|i| Bullet {
+                    delay: interval * i as f32,
+                    amplitude: *amplitude,
+                    frequency: *frequency,
+                    ..Bullet::new(origin, aim, *speed)
+                })
+                .collect(),
+            Pattern::Delayed { delay, pattern } => pattern
+                .emit(origin, target)
+                .into_iter()
+                .map(|bullet| Bullet {
+                    delay: bullet.delay + delay,
+                    ..bullet
+                })
+                .collect(),
+        }
+    }
+
+    // fires the volley from `origin` at `target` as shots of the given kind
+    pub fn fire(&self, origin: Vec2, target: Vec2, subtag: Subtype, shots: &mut Vec<Actor>) {
+        for bullet in self.emit(origin, target) {
+            shots.push(create_pattern_shot(bullet, subtag));
+        }
+    }
+}
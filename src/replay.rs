@@ -0,0 +1,194 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ggez::glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::config::AimMode;
+use crate::controls::Action;
+use crate::data::GameData;
+use crate::formation::GameMode;
+use crate::simulation::Simulation;
+
+const REPLAY_VERSION: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RecordedInput {
+    pub frame: u64,
+    pub action: Action,
+    pub pressed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RecordedVector {
+    pub frame: u64,
+    pub x: f32,
+    pub y: f32,
+}
+
+// a whole run: the seed, the number of fixed steps simulated, every action pressed or released
+// and every change of the gamepad stick, so it plays back the same whatever keys are bound.
+// the aim mode is kept too, shots go elsewhere in another one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub mode: GameMode,
+    pub aim: AimMode,
+    pub frames: u64,
+    pub inputs: Vec<RecordedInput>,
+    pub sticks: Vec<RecordedVector>,
+    pub aims: Vec<RecordedVector>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, mode: GameMode, aim: AimMode) -> Replay {
+        Replay {
+            version: REPLAY_VERSION,
+            seed,
+            mode,
+            aim,
+            frames: 0,
+            inputs: Vec::new(),
+            sticks: Vec::new(),
+            aims: Vec::new(),
+        }
+    }
+
+    pub fn record_action(&mut self, frame: u64, action: Action, pressed: bool) {
+        self.inputs.push(RecordedInput {
+            frame,
+            action,
+            pressed,
+        });
+    }
+
+    pub fn record_stick(&mut self, frame: u64, direction: Vec2) {
+        record_vector(&mut self.sticks, frame, direction);
+    }
+
+    pub fn record_aim(&mut self, frame: u64, aim: Vec2) {
+        record_vector(&mut self.aims, frame, aim);
+    }
+
+    pub fn record_frame(&mut self) {
+        self.frames += 1;
+    }
+
+    pub fn from_json(text: &str) -> io::Result<Replay> {
+        let replay: Replay = serde_json::from_str(text)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported replay version {}", replay.version),
+            ));
+        }
+        Ok(replay)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        Replay::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+}
+
+// only the last value of a frame matters, the mouse easily moves several times per frame
+fn record_vector(list: &mut Vec<RecordedVector>, frame: u64, value: Vec2) {
+    if list.last().is_some_and(|last| last.frame == frame) {
+        list.pop();
+    }
+    list.push(RecordedVector {
+        frame,
+        x: value.x,
+        y: value.y,
+    });
+}
+
+// feeds a replay back into a simulation one frame at a time
+pub struct ReplayPlayer {
+    replay: Replay,
+    next_input: usize,
+    next_stick: usize,
+    next_aim: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> ReplayPlayer {
+        ReplayPlayer {
+            replay,
+            next_input: 0,
+            next_stick: 0,
+            next_aim: 0,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.replay.seed
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.replay.mode
+    }
+
+    pub fn aim(&self) -> AimMode {
+        self.replay.aim
+    }
+
+    pub fn is_finished(&self, simulation: &Simulation) -> bool {
+        simulation.frame >= self.replay.frames
+    }
+
+    // applies the inputs recorded for the current frame and steps it, returns false once the replay ran out.
+    // the events of the last step are gone then, so nothing is played twice
+    pub fn step(&mut self, simulation: &mut Simulation) -> bool {
+        if simulation.game_over || self.is_finished(simulation) {
+            simulation.events.clear();
+            return false;
+        }
+        while let Some(input) = self.replay.inputs.get(self.next_input) {
+            if input.frame > simulation.frame {
+                break;
+            }
+            if input.pressed {
+                simulation.input.press(input.action);
+            } else {
+                simulation.input.release(input.action);
+            }
+            self.next_input += 1;
+        }
+        while let Some(stick) = self.replay.sticks.get(self.next_stick) {
+            if stick.frame > simulation.frame {
+                break;
+            }
+            simulation.input.set_stick(Vec2::new(stick.x, stick.y));
+            self.next_stick += 1;
+        }
+        while let Some(aim) = self.replay.aims.get(self.next_aim) {
+            if aim.frame > simulation.frame {
+                break;
+            }
+            simulation.input.set_aim(Vec2::new(aim.x, aim.y));
+            self.next_aim += 1;
+        }
+        simulation.step();
+        true
+    }
+}
+
+// plays a replay to the end without a window
+pub fn run_replay(replay: &Replay, data: GameData) -> Simulation {
+    let mut player = ReplayPlayer::new(replay.clone());
+    let mut simulation = Simulation::new(player.seed(), data)
+        .with_mode(player.mode())
+        .with_aim(player.aim());
+    while player.step(&mut simulation) {}
+    simulation
+}
@@ -0,0 +1,76 @@
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Text};
+use ggez::Context;
+
+use super::high_scores::draw_high_scores;
+use super::{draw_hint, draw_shade, draw_title, Playing, Scene, Shared, Title, Transition};
+use crate::controls::Action;
+use crate::events::Statistics;
+use crate::screen_size;
+
+// shown over the last frame of a finished run
+pub struct GameOver {
+    score: f32,
+    // so the run can be played again with --seed
+    seed: u64,
+    statistics: Statistics,
+    // where the run landed in the high score table, if it made it in
+    rank: Option<usize>,
+}
+
+impl GameOver {
+    pub fn new(score: f32, seed: u64, statistics: Statistics, rank: Option<usize>) -> GameOver {
+        GameOver {
+            score,
+            seed,
+            statistics,
+            rank,
+        }
+    }
+}
+
+impl Scene for GameOver {
+    fn draw(&mut self, ctx: &mut Context, shared: &mut Shared, canvas: &mut graphics::Canvas) {
+        draw_shade(ctx, canvas);
+        draw_title("GAME OVER", canvas);
+
+        let statistics = &self.statistics;
+        let stats_text = Text::new(format!(
+            "Score: {:.2}  Seed: {}\nWave: {}  Kills: {}  Bosses: {}  Hits: {}/{}  Pickups: {}  Damage taken: {}",
+            self.score,
+            self.seed,
+            statistics.waves_reached,
+            statistics.enemies_killed,
+            statistics.bosses_defeated,
+            statistics.shots_hit,
+            statistics.shots_fired,
+            statistics.pickups_collected,
+            statistics.damage_taken
+        ));
+        let position = Vec2::new(screen_size().x / 2.0 - 330.0, screen_size().y / 3.0);
+        let drawparams = graphics::DrawParam::new()
+            .dest(position)
+            .scale(Vec2::new(1.5, 1.5));
+        canvas.draw(&stats_text, drawparams);
+
+        let position = position + Vec2::new(0.0, 100.0);
+        draw_high_scores(&shared.high_scores, self.rank, position, canvas);
+        draw_hint(
+            "R or Enter to play again, Escape for the title screen",
+            canvas,
+        );
+    }
+
+    fn action_down(&mut self, shared: &mut Shared, action: Action, repeated: bool) -> Transition {
+        match action {
+            _ if repeated => Transition::None,
+            Action::Restart | Action::Confirm => Transition::Reset(Box::new(Playing::new(shared))),
+            Action::Pause => Transition::Reset(Box::new(Title::new())),
+            _ => Transition::None,
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
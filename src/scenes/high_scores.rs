@@ -0,0 +1,70 @@
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Text};
+use ggez::Context;
+
+use super::{draw_hint, draw_title, Scene, Shared, Transition};
+use crate::controls::Action;
+use crate::highscores::HighScoreTable;
+use crate::screen_size;
+
+pub struct HighScores {
+    // the entry of the run that just ended, drawn in another color
+    highlight: Option<usize>,
+}
+
+impl HighScores {
+    pub fn new(highlight: Option<usize>) -> HighScores {
+        HighScores { highlight }
+    }
+}
+
+impl Scene for HighScores {
+    fn draw(&mut self, _ctx: &mut Context, shared: &mut Shared, canvas: &mut graphics::Canvas) {
+        draw_title("HIGH SCORES", canvas);
+        let position = Vec2::new(screen_size().x / 2.0 - 330.0, screen_size().y / 3.0);
+        draw_high_scores(&shared.high_scores, self.highlight, position, canvas);
+        draw_hint("Escape to go back", canvas);
+    }
+
+    fn action_down(&mut self, _shared: &mut Shared, action: Action, repeated: bool) -> Transition {
+        match action {
+            Action::Pause | Action::Confirm | Action::Fire if !repeated => Transition::Pop,
+            _ => Transition::None,
+        }
+    }
+}
+
+pub fn draw_high_scores(
+    table: &HighScoreTable,
+    highlight: Option<usize>,
+    position: Vec2,
+    canvas: &mut graphics::Canvas,
+) {
+    if table.entries.is_empty() {
+        let drawparams = graphics::DrawParam::new()
+            .dest(position)
+            .scale(Vec2::new(1.5, 1.5));
+        canvas.draw(&Text::new("No runs yet"), drawparams);
+    }
+    for (rank, entry) in table.entries.iter().enumerate() {
+        let line = Text::new(format!(
+            "{:>2}. {:<12} {:>8.2}  {}  {:>5.0}s  seed {}",
+            rank + 1,
+            entry.name,
+            entry.score,
+            entry.date_string(),
+            entry.duration,
+            entry.seed
+        ));
+        let color = if highlight == Some(rank) {
+            graphics::Color::YELLOW
+        } else {
+            graphics::Color::WHITE
+        };
+        let drawparams = graphics::DrawParam::new()
+            .dest(position + Vec2::new(0.0, rank as f32 * 26.0))
+            .scale(Vec2::new(1.5, 1.5))
+            .color(color);
+        canvas.draw(&line, drawparams);
+    }
+}
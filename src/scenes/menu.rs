@@ -0,0 +1,60 @@
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Text};
+
+use crate::controls::Action;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuChoice {
+    // the entry was picked with Confirm or Fire
+    Confirm(usize),
+    // left or right on an entry, -1 or +1
+    Adjust(usize, i32),
+}
+
+// a vertical list of entries moved through with the movement actions, wraps around at both ends
+#[derive(Debug, Clone, PartialEq)]
+pub struct Menu {
+    pub entries: usize,
+    pub selected: usize,
+}
+
+impl Menu {
+    pub fn new(entries: usize) -> Menu {
+        Menu {
+            entries,
+            selected: 0,
+        }
+    }
+
+    pub fn handle(&mut self, action: Action) -> Option<MenuChoice> {
+        match action {
+            Action::MoveUp => {
+                self.selected = (self.selected + self.entries - 1) % self.entries;
+                None
+            }
+            Action::MoveDown => {
+                self.selected = (self.selected + 1) % self.entries;
+                None
+            }
+            Action::MoveLeft => Some(MenuChoice::Adjust(self.selected, -1)),
+            Action::MoveRight => Some(MenuChoice::Adjust(self.selected, 1)),
+            Action::Confirm | Action::Fire => Some(MenuChoice::Confirm(self.selected)),
+            _ => None,
+        }
+    }
+
+    pub fn draw(&self, labels: &[String], position: Vec2, canvas: &mut graphics::Canvas) {
+        for (index, label) in labels.iter().enumerate() {
+            let (text, color) = if index == self.selected {
+                (format!("> {}", label), graphics::Color::YELLOW)
+            } else {
+                (format!("  {}", label), graphics::Color::WHITE)
+            };
+            let drawparams = graphics::DrawParam::new()
+                .dest(position + Vec2::new(0.0, index as f32 * 50.0))
+                .scale(Vec2::new(2.5, 2.5))
+                .color(color);
+            canvas.draw(&Text::new(text), drawparams);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+mod game_over;
+mod high_scores;
+mod menu;
+mod pause;
+mod playing;
+mod settings;
+mod title;
+
+use std::path::PathBuf;
+
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Text};
+use ggez::Context;
+
+use crate::assets::Assets;
+use crate::config::AimMode;
+use crate::controls::{Action, KeyBindings};
+use crate::data::GameData;
+use crate::formation::GameMode;
+use crate::highscores::HighScoreTable;
+use crate::replay::Replay;
+use crate::sound::SoundManager;
+use crate::{screen_size, LaunchOptions};
+
+pub use game_over::GameOver;
+pub use high_scores::HighScores;
+pub use menu::{Menu, MenuChoice};
+pub use pause::Pause;
+pub use playing::Playing;
+pub use settings::Settings;
+pub use title::Title;
+
+// loaded once at startup and handed to whichever scene is running
+pub struct Shared {
+    pub options: LaunchOptions,
+    // config included, the settings scene changes it for the runs that follow
+    pub data: GameData,
+    pub assets: Assets,
+    pub sounds: SoundManager,
+    pub bindings: KeyBindings,
+    pub bindings_path: PathBuf,
+    pub high_scores: HighScoreTable,
+    pub high_score_path: PathBuf,
+    // the --replay file, read once so every restart watches it again
+    pub replay: Option<Replay>,
+    // picked on the title screen, restarts keep it
+    pub mode: GameMode,
+}
+
+// what the stack should do after a scene handled something
+pub enum Transition {
+    None,
+    // another scene on top, the current one is suspended below it
+    Push(Box<dyn Scene>),
+    Pop,
+    // throws the whole stack away, for restarts and going back to the title
+    Reset(Box<dyn Scene>),
+    Quit,
+}
+
+pub trait Scene {
+    // called once per fixed step, only for the scene on top
+    fn update(&mut self, _shared: &mut Shared) -> Transition {
+        Transition::None
+    }
+
+    fn draw(&mut self, ctx: &mut Context, shared: &mut Shared, canvas: &mut graphics::Canvas);
+
+    fn action_down(&mut self, shared: &mut Shared, action: Action, repeated: bool) -> Transition;
+
+    fn action_up(&mut self, _action: Action) {}
+
+    // analog movement and aim from a gamepad or the mouse
+    fn set_stick(&mut self, _direction: Vec2) {}
+
+    fn set_aim(&mut self, _aim: Vec2) {}
+
+    // a run keeps the aim mode it started with, Settings only changes it for the runs that follow
+    fn aim_mode(&self) -> Option<AimMode> {
+        None
+    }
+
+    // the gamepad was unplugged or the window lost focus
+    fn interrupted(&mut self) -> Transition {
+        Transition::None
+    }
+
+    // another scene was pushed on top, held input has to be let go
+    fn suspend(&mut self) {}
+
+    // the scene is removed from the stack or the game closes
+    fn leave(&mut self) {}
+
+    // overlays are drawn on top of the scene below them
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+}
+
+impl SceneStack {
+    pub fn new(first: Box<dyn Scene>) -> SceneStack {
+        SceneStack {
+            scenes: vec![first],
+        }
+    }
+
+    pub fn top(&mut self) -> &mut dyn Scene {
+        self.scenes
+            .last_mut()
+            .expect("scene stack is never empty")
+            .as_mut()
+    }
+
+    // returns false when the game should close
+    pub fn apply(&mut self, transition: Transition) -> bool {
+        match transition {
+            Transition::None => (),
+            Transition::Push(scene) => {
+                self.top().suspend();
+                self.scenes.push(scene);
+            }
+            Transition::Pop => {
+                self.top().leave();
+                self.scenes.pop();
+                if self.scenes.is_empty() {
+                    return false;
+                }
+            }
+            Transition::Reset(scene) => {
+                self.leave_all();
+                self.scenes = vec![scene];
+            }
+            Transition::Quit => return false,
+        }
+        true
+    }
+
+    pub fn leave_all(&mut self) {
+        for scene in self.scenes.iter_mut().rev() {
+            scene.leave();
+        }
+    }
+
+    // the top scene and every scene below it that still shows through, bottom first
+    pub fn visible(&mut self) -> &mut [Box<dyn Scene>] {
+        let mut first = self.scenes.len() - 1;
+        while first > 0 && self.scenes[first].is_overlay() {
+            first -= 1;
+        }
+        &mut self.scenes[first..]
+    }
+}
+
+pub fn draw_title(text: &str, canvas: &mut graphics::Canvas) {
+    let title = Text::new(text);
+    let position = Vec2::new(screen_size().x / 2.0 - 200.0, screen_size().y / 5.0);
+    let drawparams = graphics::DrawParam::new()
+        .dest(position)
+        .scale(Vec2::new(5.0, 5.0));
+    canvas.draw(&title, drawparams);
+}
+
+pub fn draw_hint(text: &str, canvas: &mut graphics::Canvas) {
+    let hint = Text::new(text);
+    let position = Vec2::new(20.0, screen_size().y - 40.0);
+    let drawparams = graphics::DrawParam::new()
+        .dest(position)
+        .scale(Vec2::new(1.5, 1.5))
+        .color(graphics::Color::new(1.0, 1.0, 1.0, 0.7));
+    canvas.draw(&hint, drawparams);
+}
+
+// darkens whatever is below an overlay
+pub fn draw_shade(ctx: &mut Context, canvas: &mut graphics::Canvas) {
+    let size = screen_size();
+    let shade = graphics::Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::fill(),
+        graphics::Rect::new(0.0, 0.0, size.x, size.y),
+        graphics::Color::new(0.0, 0.0, 0.0, 0.6),
+    );
+    if let Ok(shade) = shade {
+        canvas.draw(&shade, graphics::DrawParam::new());
+    }
+}
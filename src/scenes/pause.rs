@@ -0,0 +1,56 @@
+use ggez::glam::Vec2;
+use ggez::graphics;
+use ggez::Context;
+
+use super::{
+    draw_hint, draw_shade, draw_title, Menu, MenuChoice, Playing, Scene, Settings, Shared, Title,
+    Transition,
+};
+use crate::controls::Action;
+use crate::screen_size;
+
+const ENTRIES: [&str; 4] = ["Resume", "Restart", "Settings", "Quit to Title"];
+
+// drawn over the frozen run, nothing below it is updated so every timer stops with it
+pub struct Pause {
+    menu: Menu,
+}
+
+impl Pause {
+    pub fn new() -> Pause {
+        Pause {
+            menu: Menu::new(ENTRIES.len()),
+        }
+    }
+}
+
+impl Scene for Pause {
+    fn draw(&mut self, ctx: &mut Context, _shared: &mut Shared, canvas: &mut graphics::Canvas) {
+        draw_shade(ctx, canvas);
+        draw_title("PAUSED", canvas);
+        let labels: Vec<String> = ENTRIES.iter().map(|entry| entry.to_string()).collect();
+        let position = Vec2::new(screen_size().x / 2.0 - 120.0, screen_size().y / 2.0 - 50.0);
+        self.menu.draw(&labels, position, canvas);
+        draw_hint("Escape to resume", canvas);
+    }
+
+    fn action_down(&mut self, shared: &mut Shared, action: Action, repeated: bool) -> Transition {
+        if repeated {
+            return Transition::None;
+        }
+        if action == Action::Pause {
+            return Transition::Pop;
+        }
+        match self.menu.handle(action) {
+            Some(MenuChoice::Confirm(0)) => Transition::Pop,
+            Some(MenuChoice::Confirm(1)) => Transition::Reset(Box::new(Playing::new(shared))),
+            Some(MenuChoice::Confirm(2)) => Transition::Push(Box::new(Settings::new())),
+            Some(MenuChoice::Confirm(_)) => Transition::Reset(Box::new(Title::new())),
+            _ => Transition::None,
+        }
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
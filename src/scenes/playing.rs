@@ -0,0 +1,421 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use ggez::glam::Vec2;
+use ggez::graphics::{self, Text};
+use ggez::Context;
+
+use super::{GameOver, Pause, Scene, Shared, Transition};
+use crate::actors::{Actor, Subtype};
+use crate::animation::Animation;
+use crate::assets::Assets;
+use crate::bunkers::{BUNKER_HEIGHT, BUNKER_PIXEL, BUNKER_WIDTH};
+use crate::config::AimMode;
+use crate::controls::Action;
+use crate::events::GameEvent;
+use crate::highscores::HighScoreEntry;
+use crate::replay::{Replay, ReplayPlayer};
+use crate::screen_size;
+use crate::simulation::{Simulation, FIXED_DT};
+use crate::waves::WavePhase;
+use crate::weapons::Arsenal;
+
+// the moves and Fire are let go of when something is pushed on top of the run
+const HELD_ACTIONS: [Action; 5] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::MoveUp,
+    Action::MoveDown,
+    Action::Fire,
+];
+
+pub struct Playing {
+    simulation: Simulation,
+    recording: Option<Replay>,
+    record_path: Option<PathBuf>,
+    playback: Option<ReplayPlayer>,
+    animations: Vec<Animation>,
+    // wave number and seconds left to show its banner
+    wave_banner: (u32, f32),
+    // seconds the warning has been shown, until the boss that flies in reaches its spot
+    boss_intro: Option<f32>,
+    // one per bunker, uploaded again only when its mask changed
+    bunker_images: Vec<graphics::Image>,
+}
+
+impl Playing {
+    // without a fixed seed every run (and every restart) rolls a fresh one
+    pub fn new(shared: &mut Shared) -> Playing {
+        let playback = shared.replay.clone().map(ReplayPlayer::new);
+        let (mode, aim) = match &playback {
+            Some(player) => (player.mode(), player.aim()),
+            None => (shared.mode, shared.data.config.controls.aim),
+        };
+        let seed = match &playback {
+            Some(player) => player.seed(),
+            None => shared
+                .options
+                .seed
+                .or(shared.data.config.gameplay.seed)
+                .unwrap_or_else(rand::random),
+        };
+        let simulation = Simulation::new(seed, shared.data.clone())
+            .with_mode(mode)
+            .with_aim(aim);
+        let recording = shared
+            .options
+            .record
+            .as_ref()
+            .map(|_| Replay::new(simulation.seed, mode, aim));
+        Playing {
+            recording,
+            record_path: shared.options.record.clone(),
+            playback,
+            ..Playing::from_simulation(simulation)
+        }
+    }
+
+    // a run that is neither recorded nor played back
+    pub fn from_simulation(simulation: Simulation) -> Playing {
+        Playing {
+            simulation,
+            recording: None,
+            record_path: None,
+            playback: None,
+            animations: Vec::new(),
+            wave_banner: (0, 0.0),
+            boss_intro: None,
+            bunker_images: Vec::new(),
+        }
+    }
+
+    fn record_high_score(&mut self, shared: &mut Shared) -> Option<usize> {
+        let simulation = &self.simulation;
+        let entry = HighScoreEntry::new(
+            &shared.options.player_name(),
+            (simulation.current_score * 100.0).round() / 100.0,
+            simulation.seed,
+            simulation.frame as f32 * FIXED_DT,
+        );
+        let rank = shared.high_scores.insert(entry);
+        if rank.is_some() {
+            if let Err(e) = shared.high_scores.save(&shared.high_score_path) {
+                eprintln!(
+                    "could not save high scores to {}: {}",
+                    shared.high_score_path.display(),
+                    e
+                );
+            }
+        }
+        rank
+    }
+
+    // the recording is written once the run is over, a restart starts a new one in the same file
+    fn save_recording(&mut self) {
+        if let (Some(replay), Some(path)) = (self.recording.take(), &self.record_path) {
+            if let Err(e) = replay.save(path) {
+                eprintln!("could not save replay to {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    // the effects consumer of the simulation events
+    fn spawn_effects(&mut self, assets: &Assets) {
+        for event in &self.simulation.events {
+            match *event {
+                GameEvent::ShotHitEnemy { position, .. }
+                | GameEvent::EnemyKilled { position, .. }
+                | GameEvent::PlayerKilled { position } => {
+                    self.animations.push(Animation::new(
+                        assets.explosion_sheet.clone(),
+                        4,
+                        Duration::from_millis(100),
+                        position,
+                    ));
+                }
+                GameEvent::WaveStarted { number } => self.wave_banner = (number, 2.5),
+                GameEvent::BossSpawned { .. } => self.boss_intro = Some(0.0),
+                _ => (),
+            }
+        }
+    }
+
+    fn draw_bunkers(&mut self, ctx: &mut Context, canvas: &mut graphics::Canvas) {
+        let bunkers = &mut self.simulation.bunkers;
+        self.bunker_images.truncate(bunkers.len());
+        for (index, bunker) in bunkers.iter_mut().enumerate() {
+            if bunker.changed || index >= self.bunker_images.len() {
+                let image = graphics::Image::from_pixels(
+                    ctx,
+                    &bunker.to_rgba(),
+                    graphics::ImageFormat::Rgba8UnormSrgb,
+                    BUNKER_WIDTH as u32,
+                    BUNKER_HEIGHT as u32,
+                );
+                if index < self.bunker_images.len() {
+                    self.bunker_images[index] = image;
+                } else {
+                    self.bunker_images.push(image);
+                }
+                bunker.changed = false;
+            }
+            let drawparams = graphics::DrawParam::new()
+                .dest(bunker.position)
+                .scale(Vec2::splat(BUNKER_PIXEL));
+            canvas.draw(&self.bunker_images[index], drawparams);
+        }
+    }
+
+    // the run or the replay of it is over
+    fn finish(&mut self, shared: &mut Shared) -> Transition {
+        self.save_recording();
+        // a watched replay isn't a new run
+        let rank = match self.playback {
+            Some(_) => None,
+            None => self.record_high_score(shared),
+        };
+        Transition::Push(Box::new(GameOver::new(
+            self.simulation.current_score,
+            self.simulation.seed,
+            self.simulation.statistics.clone(),
+            rank,
+        )))
+    }
+
+    fn animation_handler(&mut self) {
+        self.wave_banner.1 -= FIXED_DT;
+        self.boss_intro = match self.boss_intro {
+            Some(shown) if self.simulation.boss_arriving() => Some(shown + FIXED_DT),
+            _ => None,
+        };
+        self.animations.retain_mut(|animation| {
+            animation.update(Duration::from_secs_f32(FIXED_DT));
+            !animation.finished
+        });
+    }
+}
+
+impl Scene for Playing {
+    fn update(&mut self, shared: &mut Shared) -> Transition {
+        if let Some(player) = &mut self.playback {
+            // recordings of runs quit early end before the game is over
+            if !player.step(&mut self.simulation) {
+                return self.finish(shared);
+            }
+        } else {
+            if let Some(replay) = &mut self.recording {
+                replay.record_frame();
+            }
+            self.simulation.step();
+        }
+
+        self.spawn_effects(&shared.assets);
+        shared.sounds.play_events(&self.simulation.events);
+        self.animation_handler();
+
+        if !self.simulation.game_over {
+            return Transition::None;
+        }
+        self.finish(shared)
+    }
+
+    fn draw(&mut self, ctx: &mut Context, shared: &mut Shared, canvas: &mut graphics::Canvas) {
+        self.draw_bunkers(ctx, canvas);
+        let assets = &mut shared.assets;
+        let simulation = &self.simulation;
+        // how far we are between the last step and the next one
+        let alpha = (ctx.time.remaining_update_time().as_secs_f32() / FIXED_DT).min(1.0);
+        let interpolate = |actor: &Actor| actor.previous_position.lerp(actor.position, alpha);
+        let player = &simulation.player;
+        // blinks five times a second while it can't be hit
+        let blink_off = (simulation.invulnerable * 10.0) as i32 % 2 == 1;
+        if !blink_off {
+            draw_actor(assets, canvas, player, interpolate(player), player.rotation);
+        }
+        for enemy in &simulation.enemies {
+            draw_actor(assets, canvas, enemy, interpolate(enemy), enemy.rotation);
+        }
+        for shot in &simulation.shots {
+            draw_actor(assets, canvas, shot, interpolate(shot), shot.rotation);
+        }
+        // drawing shield (if any are picked up at all)
+        for i in 1..simulation.equipped_shields + 1 {
+            let image = assets.shield_ui_sprite.clone();
+            draw_ui_element(&image, canvas, i);
+        }
+        draw_scoreboard(simulation.current_score, canvas);
+        draw_lives(simulation.lives, canvas);
+        draw_weapon(&simulation.arsenal, canvas);
+        if let Some((boss, full_life)) = simulation.boss() {
+            draw_boss_bar(ctx, boss, full_life, canvas);
+        }
+        if let Some(shown) = self.boss_intro {
+            // blinks twice a second
+            if (shown * 4.0) as i32 % 2 == 0 {
+                draw_wave_banner("WARNING", canvas);
+            }
+        } else if self.wave_banner.1 > 0.0 {
+            draw_wave_banner(&format!("Wave {}", self.wave_banner.0), canvas);
+        } else if let (WavePhase::Waiting(remaining), 1..) =
+            (simulation.waves.phase(), simulation.waves.wave_number())
+        {
+            draw_wave_banner(&format!("Next wave in {}", remaining.ceil()), canvas);
+        }
+
+        for animation in &mut self.animations {
+            animation.draw(ctx, canvas);
+        }
+    }
+
+    fn action_down(&mut self, _shared: &mut Shared, action: Action, repeated: bool) -> Transition {
+        match action {
+            Action::Pause if !repeated => Transition::Push(Box::new(Pause::new())),
+            _ if self.playback.is_none() && !repeated => {
+                if let Some(replay) = &mut self.recording {
+                    replay.record_action(self.simulation.frame, action, true);
+                }
+                self.simulation.input.press(action);
+                Transition::None
+            }
+            _ => Transition::None,
+        }
+    }
+
+    fn action_up(&mut self, action: Action) {
+        if self.playback.is_none() {
+            if let Some(replay) = &mut self.recording {
+                replay.record_action(self.simulation.frame, action, false);
+            }
+            self.simulation.input.release(action);
+        }
+    }
+
+    fn set_stick(&mut self, direction: Vec2) {
+        if self.playback.is_none() {
+            if let Some(replay) = &mut self.recording {
+                replay.record_stick(self.simulation.frame, direction);
+            }
+            self.simulation.input.set_stick(direction);
+        }
+    }
+
+    fn set_aim(&mut self, aim: Vec2) {
+        if self.playback.is_none() {
+            if let Some(replay) = &mut self.recording {
+                replay.record_aim(self.simulation.frame, aim);
+            }
+            self.simulation.input.set_aim(aim);
+        }
+    }
+
+    fn aim_mode(&self) -> Option<AimMode> {
+        Some(self.simulation.data.config.controls.aim)
+    }
+
+    // losing the controller or the window mid-run shouldn't cost the run
+    fn interrupted(&mut self) -> Transition {
+        Transition::Push(Box::new(Pause::new()))
+    }
+
+    fn suspend(&mut self) {
+        for action in HELD_ACTIONS {
+            self.action_up(action);
+        }
+        self.set_stick(Vec2::ZERO);
+    }
+
+    fn leave(&mut self) {
+        self.save_recording();
+    }
+}
+
+fn draw_scoreboard(current_score: f32, canvas: &mut graphics::Canvas) {
+    let rounded_score = (current_score * 100.0).round() / 100.0;
+    let score_text = Text::new(format!("Score: {}", rounded_score));
+    let position = Vec2::new(screen_size().x / 2.0 - 50.0, 10.0);
+
+    let drawparams = graphics::DrawParam::new()
+        .dest(position)
+        .scale(Vec2::new(2.0, 2.0));
+    canvas.draw(&score_text, drawparams);
+}
+fn boss_name(subtype: Subtype) -> &'static str {
+    match subtype {
+        Subtype::TankEnemy => "TANK",
+        _ => "MOTHERSHIP",
+    }
+}
+fn draw_boss_bar(ctx: &mut Context, boss: &Actor, full_life: f32, canvas: &mut graphics::Canvas) {
+    let width = screen_size().x / 2.0;
+    let frame = graphics::Rect::new(screen_size().x / 4.0, 55.0, width, 16.0);
+    let mut fill = frame;
+    fill.w = width * (boss.life_points / full_life).clamp(0.0, 1.0);
+    let background = graphics::Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::fill(),
+        frame,
+        graphics::Color::new(0.2, 0.0, 0.0, 0.8),
+    );
+    let health =
+        graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), fill, graphics::Color::RED);
+    if let (Ok(background), Ok(health)) = (background, health) {
+        canvas.draw(&background, graphics::DrawParam::new());
+        canvas.draw(&health, graphics::DrawParam::new());
+    }
+    let drawparams = graphics::DrawParam::new()
+        .dest(Vec2::new(frame.x, frame.y - 30.0))
+        .scale(Vec2::new(2.0, 2.0));
+    canvas.draw(&Text::new(boss_name(boss.subtag)), drawparams);
+}
+fn draw_lives(lives: u32, canvas: &mut graphics::Canvas) {
+    let lives_text = Text::new(format!("Lives: {}", lives));
+    let position = Vec2::new(screen_size().x - 150.0, 10.0);
+    let drawparams = graphics::DrawParam::new()
+        .dest(position)
+        .scale(Vec2::new(2.0, 2.0));
+    canvas.draw(&lives_text, drawparams);
+}
+// bottom left, the weapon in hand and how far it's upgraded
+fn draw_weapon(arsenal: &Arsenal, canvas: &mut graphics::Canvas) {
+    let stats = arsenal.weapon().stats();
+    let weapon_text = Text::new(format!(
+        "{}  Lv {}/{}",
+        stats.name,
+        arsenal.level(),
+        stats.max_level
+    ));
+    let position = Vec2::new(10.0, screen_size().y - 40.0);
+    let drawparams = graphics::DrawParam::new()
+        .dest(position)
+        .scale(Vec2::new(2.0, 2.0));
+    canvas.draw(&weapon_text, drawparams);
+}
+fn draw_wave_banner(text: &str, canvas: &mut graphics::Canvas) {
+    let banner_text = Text::new(text);
+    let position = Vec2::new(screen_size().x / 2.0 - 100.0, screen_size().y / 3.0);
+    let drawparams = graphics::DrawParam::new()
+        .dest(position)
+        .scale(Vec2::new(4.0, 4.0));
+    canvas.draw(&banner_text, drawparams);
+}
+fn draw_ui_element(image: &graphics::Image, canvas: &mut graphics::Canvas, index: i32) {
+    let drawparams = graphics::DrawParam::new()
+        .dest(Vec2::new(10.0 + (((index - 1) as f32) * 75.0), 10.0))
+        .scale(Vec2::new(5.5, 5.5));
+    canvas.draw(image, drawparams);
+}
+fn draw_actor(
+    assets: &mut Assets,
+    canvas: &mut graphics::Canvas,
+    actor: &Actor,
+    coords: Vec2,
+    rotation: f32,
+) {
+    let image = assets.actor_image(actor);
+    let drawparams = graphics::DrawParam::new()
+        .dest(coords + 0.5)
+        .scale(Vec2::new(5.5, 5.5))
+        .rotation(rotation)
+        .offset(Vec2::new(0.5, 0.5));
+    canvas.draw(image, drawparams);
+}
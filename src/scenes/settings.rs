@@ -0,0 +1,91 @@
+use ggez::glam::Vec2;
+use ggez::graphics;
+use ggez::Context;
+
+use super::{draw_hint, draw_title, Menu, MenuChoice, Scene, Shared, Transition};
+use crate::config::AimMode;
+use crate::controls::{Action, KeyBindings, LAYOUTS};
+use crate::screen_size;
+
+const AIM_MODES: [AimMode; 3] = [AimMode::Fixed, AimMode::Mouse, AimMode::Stick];
+const VOLUME_STEP: f32 = 0.1;
+const BACK: usize = 5;
+
+// changes apply to the runs that follow, the layout is also written to the key bindings file
+pub struct Settings {
+    menu: Menu,
+}
+
+impl Settings {
+    pub fn new() -> Settings {
+        Settings {
+            menu: Menu::new(BACK + 1),
+        }
+    }
+}
+
+// steps through a list of options and wraps around at both ends
+fn cycle<T: PartialEq + Copy>(options: &[T], current: T, step: i32) -> T {
+    let index = options.iter().position(|&o| o == current).unwrap_or(0) as i32;
+    options[(index + step).rem_euclid(options.len() as i32) as usize]
+}
+
+fn adjust_volume(volume: &mut f32, step: i32) {
+    *volume = ((*volume + step as f32 * VOLUME_STEP) * 10.0).round() / 10.0;
+    *volume = volume.clamp(0.0, 1.0);
+}
+
+impl Scene for Settings {
+    fn draw(&mut self, _ctx: &mut Context, shared: &mut Shared, canvas: &mut graphics::Canvas) {
+        draw_title("SETTINGS", canvas);
+        let controls = &shared.data.config.controls;
+        let audio = &shared.sounds.settings;
+        let labels = vec![
+            format!("Aim: {:?}", controls.aim),
+            format!("Keys: {}", controls.layout),
+            format!("Volume: {:.0}%", audio.master_volume * 100.0),
+            format!("Effects: {:.0}%", audio.sfx_volume * 100.0),
+            format!("Muted: {}", if audio.muted { "yes" } else { "no" }),
+            "Back".to_string(),
+        ];
+        let position = Vec2::new(screen_size().x / 2.0 - 200.0, screen_size().y / 2.0 - 100.0);
+        self.menu.draw(&labels, position, canvas);
+        draw_hint("Left/Right to change, Escape to go back", canvas);
+    }
+
+    fn action_down(&mut self, shared: &mut Shared, action: Action, repeated: bool) -> Transition {
+        if action == Action::Pause && !repeated {
+            return Transition::Pop;
+        }
+        let (entry, step) = match self.menu.handle(action) {
+            Some(MenuChoice::Confirm(_)) if repeated => return Transition::None,
+            Some(MenuChoice::Confirm(BACK)) => return Transition::Pop,
+            Some(MenuChoice::Confirm(entry)) => (entry, 1),
+            Some(MenuChoice::Adjust(entry, step)) => (entry, step),
+            None => return Transition::None,
+        };
+        let controls = &mut shared.data.config.controls;
+        let audio = &mut shared.sounds.settings;
+        match entry {
+            0 => controls.aim = cycle(&AIM_MODES, controls.aim, step),
+            1 => {
+                controls.layout = cycle(&LAYOUTS, controls.layout.as_str(), step).to_string();
+                if let Some(bindings) = KeyBindings::preset(&controls.layout) {
+                    shared.bindings = bindings;
+                }
+                if let Err(e) = shared.bindings.save(&shared.bindings_path) {
+                    eprintln!(
+                        "could not save key bindings to {}: {}",
+                        shared.bindings_path.display(),
+                        e
+                    );
+                }
+            }
+            2 => adjust_volume(&mut audio.master_volume, step),
+            3 => adjust_volume(&mut audio.sfx_volume, step),
+            4 => audio.muted = !audio.muted,
+            _ => (),
+        }
+        Transition::None
+    }
+}
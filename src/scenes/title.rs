@@ -0,0 +1,52 @@
+use ggez::glam::Vec2;
+use ggez::graphics;
+use ggez::Context;
+
+use super::{
+    draw_hint, draw_title, HighScores, Menu, MenuChoice, Playing, Scene, Settings, Shared,
+    Transition,
+};
+use crate::controls::Action;
+use crate::formation::GameMode;
+use crate::screen_size;
+
+const MODES: [GameMode; 2] = [GameMode::Survival, GameMode::Formation];
+const ENTRIES: [&str; 5] = ["Survival", "Formation", "High Scores", "Settings", "Quit"];
+
+pub struct Title {
+    menu: Menu,
+}
+
+impl Title {
+    pub fn new() -> Title {
+        Title {
+            menu: Menu::new(ENTRIES.len()),
+        }
+    }
+}
+
+impl Scene for Title {
+    fn draw(&mut self, _ctx: &mut Context, _shared: &mut Shared, canvas: &mut graphics::Canvas) {
+        draw_title("SPACE INVADERS", canvas);
+        let labels: Vec<String> = ENTRIES.iter().map(|entry| entry.to_string()).collect();
+        let position = Vec2::new(screen_size().x / 2.0 - 120.0, screen_size().y / 2.0 - 50.0);
+        self.menu.draw(&labels, position, canvas);
+        draw_hint("Up/Down to choose, Enter or Space to pick", canvas);
+    }
+
+    fn action_down(&mut self, shared: &mut Shared, action: Action, repeated: bool) -> Transition {
+        if repeated {
+            return Transition::None;
+        }
+        match self.menu.handle(action) {
+            Some(MenuChoice::Confirm(entry @ 0..=1)) => {
+                shared.mode = MODES[entry];
+                Transition::Reset(Box::new(Playing::new(shared)))
+            }
+            Some(MenuChoice::Confirm(2)) => Transition::Push(Box::new(HighScores::new(None))),
+            Some(MenuChoice::Confirm(3)) => Transition::Push(Box::new(Settings::new())),
+            Some(MenuChoice::Confirm(_)) => Transition::Quit,
+            _ => Transition::None,
+        }
+    }
+}
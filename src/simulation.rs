@@ -0,0 +1,707 @@
+use ggez::glam::Vec2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::actors::{
+    boss_entry, create_boss, create_enemy, create_player, create_shot, Actor, Subtype, TypeActor,
+};
+use crate::behaviour::{
+    basic_enemy_behaviour, boss_enemy_behaviour, kamikaze_behaviour, tank_enemy_behaviour,
+    update_basic_enemy_movement, update_player_position, update_shot_movement,
+};
+use crate::bunkers::{create_bunkers, Bunker};
+use crate::config::{AimMode, GameConfig};
+use crate::controls::Action;
+use crate::data::GameData;
+use crate::enemies::Steering;
+use crate::events::{GameEvent, Statistics};
+use crate::formation::{front_row, is_invader, Formation, GameMode, INVADER_FIRE_INTERVAL};
+use crate::grid::SpatialGrid;
+use crate::helpers::{
+    clamp_player, make_rand_pos, offscreen_position_on, random_offscreen_position,
+};
+use crate::screen_size;
+use crate::waves::WaveDirector;
+use crate::weapons::Arsenal;
+
+// every system advances in steps of this size, independent of the monitor refresh rate
+pub const UPDATES_PER_SECOND: u32 = 60;
+pub const FIXED_DT: f32 = 1.0 / UPDATES_PER_SECOND as f32;
+
+// roughly twice the largest hit circle, so most checks only look at a handful of cells
+const COLLISION_CELL_SIZE: f32 = 64.0;
+// a boss this close to its entry spot has arrived, the behaviours pick a new one within 1.5
+const BOSS_ARRIVAL_DISTANCE: f32 = 2.0;
+
+pub struct InputState {
+    pub velocity: Vec2,
+    pub is_firing: bool,
+    // set by the switch key, the next step hands over the next weapon
+    pub switch_weapon: bool,
+    speed: f32,
+    // movement actions currently held, oldest first
+    held: Vec<Action>,
+    // analog direction from a gamepad, at most unit length
+    stick: Vec2,
+    // cursor position or right stick direction, depending on the aim mode,
+    // None until the first one arrives
+    aim: Option<Vec2>,
+}
+
+impl InputState {
+    pub fn new(config: &GameConfig) -> InputState {
+        InputState {
+            velocity: Vec2::new(0.0, 0.0),
+            is_firing: false,
+            switch_weapon: false,
+            speed: config.gameplay.player_speed,
+            held: Vec::new(),
+            stick: Vec2::ZERO,
+            aim: None,
+        }
+    }
+
+    pub fn press(&mut self, action: Action) {
+        match action {
+            Action::MoveLeft | Action::MoveRight | Action::MoveUp | Action::MoveDown => {
+                self.held.retain(|&held| held != action);
+                self.held.push(action);
+            }
+            Action::Fire => self.is_firing = true,
+            Action::SwitchWeapon => self.switch_weapon = true,
+            _ => (),
+        }
+    }
+
+    pub fn release(&mut self, action: Action) {
+        match action {
+            Action::Fire => self.is_firing = false,
+            _ => self.held.retain(|&held| held != action),
+        }
+    }
+
+    pub fn set_stick(&mut self, direction: Vec2) {
+        self.stick = direction.clamp_length_max(1.0);
+    }
+
+    pub fn set_aim(&mut self, aim: Vec2) {
+        self.aim = Some(aim);
+    }
+
+    // at most unit length, held directions win over the stick,
+    // when both keys of an axis are held the one pressed last wins
+    pub fn movement(&self) -> Vec2 {
+        let latest = |negative: Action, positive: Action| match self
+            .held
+            .iter()
+            .rev()
+            .find(|&&held| held == negative || held == positive)
+        {
+            Some(&held) if held == negative => -1.0,
+            Some(_) => 1.0,
+            None => 0.0,
+        };
+        let direction = Vec2::new(
+            latest(Action::MoveLeft, Action::MoveRight),
+            latest(Action::MoveUp, Action::MoveDown),
+        );
+        if direction == Vec2::ZERO {
+            return self.stick;
+        }
+        direction.normalize()
+    }
+
+    pub fn update_velocity(&mut self) {
+        self.velocity = self.movement() * self.speed;
+    }
+}
+
+pub struct Simulation {
+    pub player: Actor,
+    pub enemies: Vec<Actor>,
+    pub shots: Vec<Actor>,
+    pub input: InputState,
+    pub arsenal: Arsenal,
+    pub equipped_shields: i32,
+    pub lives: u32,
+    // seconds left in which the player can't be hit, the renderer blinks the ship meanwhile
+    pub invulnerable: f32,
+    pub current_score: f32,
+    pub game_over: bool,
+    // events of the last step, read by the renderer and audio after every step
+    pub events: Vec<GameEvent>,
+    pub statistics: Statistics,
+    pub seed: u64,
+    pub frame: u64,
+    rng: StdRng,
+    pub data: GameData,
+    pub waves: WaveDirector,
+    // added on top of every archetype speed, grows with each spawn
+    enemy_speed_bonus: f32,
+    is_boss_present: bool,
+    // the purple boss and the tank take turns
+    next_boss: Subtype,
+    pub mode: GameMode,
+    formation: Formation,
+    pub bunkers: Vec<Bunker>,
+    invader_fire_timer: f32,
+    enemy_grid: SpatialGrid,
+    ability_timer: (f32, f32),
+    asteroid_timer: (f32, f32),
+}
+
+impl Simulation {
+    // every random roll of a run comes from this seed, so a run can be reproduced
+    pub fn new(seed: u64, data: GameData) -> Simulation {
+        let gameplay = &data.config.gameplay;
+        let ability_timer = (0.0, gameplay.pickup_interval);
+        let asteroid_timer = (0.0, gameplay.asteroid_interval);
+        Simulation {
+            player: create_player(),
+            enemies: Vec::new(),
+            shots: Vec::new(),
+            input: InputState::new(&data.config),
+            arsenal: Arsenal::new(&data.weapons),
+            equipped_shields: 0,
+            lives: data.config.gameplay.lives,
+            invulnerable: 0.0,
+            current_score: 0.0,
+            game_over: false,
+            events: Vec::new(),
+            statistics: Statistics::default(),
+            seed,
+            frame: 0,
+            rng: StdRng::seed_from_u64(seed),
+            waves: WaveDirector::new(data.waves.clone()),
+            data,
+            enemy_speed_bonus: 0.0,
+            is_boss_present: false,
+            next_boss: Subtype::BossEnemy,
+            mode: GameMode::Survival,
+            formation: Formation::new(),
+            bunkers: Vec::new(),
+            invader_fire_timer: 0.0,
+            enemy_grid: SpatialGrid::new(COLLISION_CELL_SIZE),
+            ability_timer,
+            asteroid_timer,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: GameMode) -> Simulation {
+        self.mode = mode;
+        // the formation run starts below the bunkers, where every respawn happens
+        if mode == GameMode::Formation {
+            self.player.position = respawn_position();
+            self.player.previous_position = self.player.position;
+        }
+        self
+    }
+
+    // replays bring the aim mode they were recorded with, whatever the config says now
+    pub fn with_aim(mut self, aim: AimMode) -> Simulation {
+        self.data.config.controls.aim = aim;
+        self
+    }
+
+    pub fn step(&mut self) {
+        if self.game_over {
+            return;
+        }
+        let dt = FIXED_DT;
+        self.events.clear();
+        self.store_previous_positions();
+        self.invulnerable = (self.invulnerable - dt).max(0.0);
+
+        //Handles player movement
+        self.input.update_velocity();
+        update_player_position(
+            &mut self.player,
+            &mut self.input,
+            &self.data.config.gameplay,
+            dt,
+        );
+        if let Some(aim) = self.aim_direction() {
+            self.player.rotation = aim.x.atan2(-aim.y);
+        }
+
+        //clamps player to screen size
+        clamp_player(&mut self.player);
+
+        //Enemy wave spawn script
+        match self.mode {
+            GameMode::Survival => self.spawn_enemy(dt),
+            GameMode::Formation => self.march_formation(dt),
+        }
+        // the formation landed
+        if self.game_over {
+            return;
+        }
+        update_basic_enemy_movement(&mut self.enemies, dt);
+        self.trigger_enemy_ability(dt);
+
+        //Player shot spawn
+        self.spawn_player_shot(dt);
+        update_shot_movement(&mut self.shots, dt);
+
+        self.spawn_asteroid(dt);
+
+        //Handles collision events
+        self.handle_collision();
+        self.apply_events();
+        self.record_statistics();
+
+        self.current_score += dt;
+
+        // Boss Spawn script
+        if self.mode == GameMode::Survival {
+            self.spawn_boss();
+        }
+
+        self.spawn_behaviour(dt);
+
+        self.frame += 1;
+    }
+
+    // kept so the renderer can interpolate between the last two steps
+    fn store_previous_positions(&mut self) {
+        self.player.previous_position = self.player.position;
+        for actor in self.enemies.iter_mut().chain(self.shots.iter_mut()) {
+            actor.previous_position = actor.position;
+        }
+    }
+
+    fn spawn_enemy(&mut self, dt: f32) {
+        let enemies_alive = self
+            .enemies
+            .iter()
+            .filter(|enemy| matches!(enemy.subtag, Subtype::BasicEnemy | Subtype::KamikazeEnemy))
+            .count();
+        let mut spawns = Vec::new();
+        if let Some(number) = self.waves.update(dt, enemies_alive, &mut spawns) {
+            self.events.push(GameEvent::WaveStarted { number });
+        }
+        for order in spawns {
+            let position = match order.edge {
+                Some(edge) => {
+                    offscreen_position_on(&mut self.rng, edge, screen_size().x, screen_size().y)
+                }
+                None => random_offscreen_position(&mut self.rng, screen_size().x, screen_size().y),
+            };
+            let destination = make_rand_pos(&mut self.rng);
+            let archetype = order
+                .archetype
+                .unwrap_or_else(|| self.data.enemies.pick(&mut self.rng));
+            let enemy = create_enemy(
+                &self.data.enemies,
+                archetype,
+                position,
+                destination,
+                self.enemy_speed_bonus,
+                self.current_score,
+            );
+            self.enemies.push(enemy);
+            //difficulty enhancer
+            self.enemy_speed_bonus += 0.04;
+        }
+    }
+
+    // a new formation whenever the last one is gone, only its front row fires
+    fn march_formation(&mut self, dt: f32) {
+        if !self.enemies.iter().any(is_invader) {
+            let invaders = self.formation.spawn(&self.data.enemies, self.current_score);
+            // every formation finds the bunkers rebuilt
+            self.bunkers = create_bunkers();
+            self.events.push(GameEvent::WaveStarted {
+                number: self.formation.level,
+            });
+            self.enemies.extend(invaders);
+        }
+        self.formation.update(&mut self.enemies, dt);
+
+        // landing ends the run whatever lives are left
+        let landed = self
+            .enemies
+            .iter()
+            .any(|enemy| is_invader(enemy) && enemy.position.y >= screen_size().y - 150.0);
+        if landed {
+            self.events.push(GameEvent::PlayerKilled {
+                position: self.player.position,
+            });
+            self.lives = 0;
+            self.player = Actor::new();
+            self.game_over = true;
+            return;
+        }
+
+        self.invader_fire_timer += dt;
+        if self.invader_fire_timer < INVADER_FIRE_INTERVAL {
+            return;
+        }
+        self.invader_fire_timer = 0.0;
+        let shooters = front_row(&self.enemies);
+        if shooters.is_empty() {
+            return;
+        }
+        let shooter = &self.enemies[shooters[self.rng.gen_range(0..shooters.len())]];
+        let source_pos = shooter.position + Vec2::new(0.0, 20.0);
+        let dest_pos = Vec2::new(source_pos.x, screen_size().y * 2.0);
+        let shot = create_shot(&mut self.rng, source_pos, dest_pos, Subtype::EnemyShot);
+        self.shots.push(shot);
+    }
+
+    fn spawn_asteroid(&mut self, dt: f32) {
+        self.asteroid_timer.0 += dt;
+        if self.asteroid_timer.0 >= self.asteroid_timer.1 {
+            let source_pos =
+                random_offscreen_position(&mut self.rng, screen_size().x, screen_size().y);
+            let destination =
+                random_offscreen_position(&mut self.rng, screen_size().x, screen_size().y);
+            let shot = create_shot(
+                &mut self.rng,
+                source_pos,
+                destination,
+                Subtype::AsteroidShot,
+            );
+            self.shots.push(shot);
+            self.asteroid_timer.0 = 0.0;
+        }
+    }
+
+    // unit direction shots are aimed at, None when they just go up the screen.
+    // straight up as well as long as there's nothing to aim with
+    fn aim_direction(&self) -> Option<Vec2> {
+        let aim = match (self.data.config.controls.aim, self.input.aim) {
+            (AimMode::Fixed, _) => return None,
+            (_, None) => Vec2::ZERO,
+            (AimMode::Mouse, Some(cursor)) => cursor - self.player.position,
+            (AimMode::Stick, Some(direction)) => direction,
+        };
+        Some(aim.try_normalize().unwrap_or(Vec2::new(0.0, -1.0)))
+    }
+
+    fn spawn_player_shot(&mut self, dt: f32) {
+        if std::mem::take(&mut self.input.switch_weapon) {
+            self.arsenal.switch();
+        }
+        let aim = self.aim_direction();
+        let source_pos = Vec2::new(self.player.position.x, self.player.position.y - 2.0);
+        let volley = self
+            .arsenal
+            .update(dt, self.input.is_firing, source_pos, aim);
+        if let Some(shot) = volley.first() {
+            self.events.push(GameEvent::ShotFired {
+                subtype: shot.subtag,
+                position: source_pos,
+            });
+        }
+        self.shots.extend(volley);
+    }
+
+    // only detects what touched what, the reactions live in the consumers of `events`
+    pub fn handle_collision(&mut self) {
+        self.enemy_grid.rebuild(&self.enemies);
+        let mut nearby = Vec::new();
+        // several hits in one step still cost a single point
+        let invulnerable = self.invulnerable > 0.0;
+        let mut player_hit = false;
+
+        for shot in &mut self.shots {
+            //bunkers soak up shots from both sides, checked along the whole way of the step
+            if matches!(
+                shot.subtag,
+                Subtype::BasicShot
+                    | Subtype::LaserShot
+                    | Subtype::MissileShot
+                    | Subtype::EnemyShot
+                    | Subtype::BossShot
+            ) {
+                for bunker in &mut self.bunkers {
+                    if let Some((x, y)) = bunker.hit(shot.previous_position, shot.position) {
+                        bunker.carve(x, y);
+                        shot.life_points = 0.0;
+                        break;
+                    }
+                }
+                if shot.life_points <= 0.0 {
+                    continue;
+                }
+            }
+
+            //player to shot collision handle script
+            let distance_to_player = self.player.position - shot.position;
+            if distance_to_player.length() < self.player.box_size + shot.box_size {
+                match shot.subtag {
+                    Subtype::EnemyShot | Subtype::BossShot | Subtype::AsteroidShot
+                        if !invulnerable =>
+                    {
+                        shot.life_points = 0.0;
+                        player_hit = true;
+                    }
+                    Subtype::BasicCountBuff
+                    | Subtype::BasicReloadBuff
+                    | Subtype::BasicShieldBuff => {
+                        shot.life_points = 0.0;
+                        self.events.push(GameEvent::PickupCollected {
+                            subtype: shot.subtag,
+                            position: shot.position,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+
+            //enemy to shot collision handle script, only enemies sharing a grid cell are tested
+            if !matches!(
+                shot.subtag,
+                Subtype::BasicShot
+                    | Subtype::LaserShot
+                    | Subtype::MissileShot
+                    | Subtype::AsteroidShot
+            ) {
+                continue;
+            }
+            self.enemy_grid
+                .query(shot.position, shot.box_size, &mut nearby);
+            for &index in &nearby {
+                if shot.life_points <= 0.0 {
+                    break;
+                }
+                let enemy = &mut self.enemies[index];
+                if enemy.life_points <= 0.0 {
+                    continue;
+                }
+                let distance_to_enemy = enemy.position - shot.position;
+                if distance_to_enemy.length() >= enemy.box_size + shot.box_size {
+                    continue;
+                }
+                match enemy.subtag {
+                    Subtype::BasicEnemy | Subtype::KamikazeEnemy | Subtype::InvaderEnemy => {
+                        enemy.life_points -= shot.damage;
+                    }
+                    Subtype::BossEnemy | Subtype::TankEnemy => {
+                        shot.life_points = 0.0;
+                        enemy.life_points -= shot.damage * 3.0;
+                    }
+                    _ => (),
+                }
+                // lasers and asteroids go on through
+                if let Subtype::BasicShot | Subtype::MissileShot = shot.subtag {
+                    shot.life_points = 0.0;
+                }
+                self.events.push(GameEvent::ShotHitEnemy {
+                    shot: shot.subtag,
+                    enemy: enemy.subtag,
+                    position: enemy.position,
+                });
+                if enemy.life_points <= 0.0 {
+                    self.events.push(GameEvent::EnemyKilled {
+                        subtype: enemy.subtag,
+                        position: enemy.position,
+                    });
+                    if let Subtype::BossEnemy | Subtype::TankEnemy = enemy.subtag {
+                        self.events.push(GameEvent::BossDefeated {
+                            subtype: enemy.subtag,
+                            position: enemy.position,
+                        });
+                    }
+                }
+            }
+        }
+
+        //player to enemy collision handle script
+        for enemy in &mut self.enemies {
+            // already shot down this step
+            if enemy.life_points <= 0.0 {
+                continue;
+            }
+            let distance_to_player = self.player.position - enemy.position;
+            if !invulnerable && distance_to_player.length() < enemy.box_size + self.player.box_size
+            {
+                if let Subtype::KamikazeEnemy | Subtype::BasicEnemy | Subtype::InvaderEnemy =
+                    enemy.subtag
+                {
+                    enemy.life_points = 0.0;
+                    self.events.push(GameEvent::EnemyKilled {
+                        subtype: enemy.subtag,
+                        position: enemy.position,
+                    });
+                }
+                player_hit = true;
+            }
+        }
+        if player_hit {
+            self.events.push(GameEvent::PlayerDamaged {
+                position: self.player.position,
+            });
+        }
+        self.handle_life_state();
+    }
+
+    // gameplay reactions: damage, buffs and the boss flag
+    fn apply_events(&mut self) {
+        let gameplay = &self.data.config.gameplay;
+        for event in &self.events {
+            match *event {
+                GameEvent::PlayerDamaged { .. } => {
+                    self.player.life_points -= 1.0;
+                    self.equipped_shields -= 1;
+                    self.invulnerable = gameplay.invulnerability_time;
+                }
+                GameEvent::PickupCollected { subtype, .. } => match subtype {
+                    Subtype::BasicCountBuff => {
+                        self.arsenal.upgrade();
+                    }
+                    Subtype::BasicReloadBuff => self.arsenal.reduce_cooldowns(gameplay.reload_buff),
+                    Subtype::BasicShieldBuff
+                        if self.player.life_points < gameplay.max_life_points =>
+                    {
+                        self.player.life_points += gameplay.shield_buff;
+                        self.equipped_shields += 1;
+                    }
+                    _ => (),
+                },
+                GameEvent::BossDefeated { .. } => self.is_boss_present = false,
+                _ => (),
+            }
+        }
+        if self.player.life_points <= 0.0 && self.is_player_alive() {
+            self.events.push(GameEvent::PlayerKilled {
+                position: self.player.position,
+            });
+            self.lives -= 1;
+            if self.lives == 0 {
+                self.player = Actor::new();
+                self.game_over = true;
+            } else {
+                self.respawn_player();
+            }
+        }
+    }
+
+    // a fresh ship at the bottom centre, weapon upgrades are kept but shields are gone
+    fn respawn_player(&mut self) {
+        let position = respawn_position();
+        self.player = create_player();
+        self.player.position = position;
+        self.player.previous_position = position;
+        self.equipped_shields = 0;
+    }
+
+    fn record_statistics(&mut self) {
+        for event in &self.events {
+            self.statistics.record(event);
+        }
+    }
+
+    //could optimise this, but no need at current moment
+    fn handle_life_state(&mut self) {
+        let predicate = |actor: &Actor| actor.life_points > 0.0;
+        self.shots.retain(&predicate);
+        self.enemies.retain(&predicate);
+    }
+
+    fn trigger_enemy_ability(&mut self, dt: f32) {
+        for enemy in &mut self.enemies {
+            match enemy.subtag {
+                Subtype::BasicEnemy => {
+                    basic_enemy_behaviour(enemy, &mut self.player, &mut self.shots, &mut self.rng);
+                }
+                Subtype::KamikazeEnemy => {
+                    // later spawns are faster, like every other enemy
+                    let archetype = self.data.enemies.get(enemy.archetype);
+                    let steering = Steering {
+                        max_speed: archetype.steering.max_speed
+                            * (1.0 + enemy.speed_bonus / archetype.speed),
+                        ..archetype.steering
+                    };
+                    kamikaze_behaviour(enemy, self.player.position, &steering, dt);
+                }
+                Subtype::BossEnemy => {
+                    boss_enemy_behaviour(
+                        enemy,
+                        &mut self.player,
+                        &mut self.shots,
+                        &mut self.rng,
+                        dt,
+                    );
+                }
+                Subtype::TankEnemy => {
+                    tank_enemy_behaviour(
+                        enemy,
+                        &mut self.player,
+                        &mut self.shots,
+                        &mut self.rng,
+                        dt,
+                    );
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn spawn_boss(&mut self) {
+        if ((self.current_score.ceil() % 40.0) == 0.0) & (!self.is_boss_present) {
+            self.is_boss_present = true;
+            let gameplay = &self.data.config.gameplay;
+            let boss = match self.next_boss {
+                Subtype::TankEnemy => create_boss(Subtype::TankEnemy, gameplay.tank_life_points),
+                _ => create_boss(Subtype::BossEnemy, gameplay.boss_life_points),
+            };
+            self.next_boss = match boss.subtag {
+                Subtype::TankEnemy => Subtype::BossEnemy,
+                _ => Subtype::TankEnemy,
+            };
+            self.events.push(GameEvent::BossSpawned {
+                subtype: boss.subtag,
+                position: boss.position,
+            });
+            self.enemies.push(boss);
+        }
+    }
+
+    fn spawn_behaviour(&mut self, dt: f32) {
+        self.ability_timer.0 += dt;
+        if self.ability_timer.0 >= self.ability_timer.1 {
+            let x = self.rng.gen_range(5.0..screen_size().x);
+            let y = -10.0;
+            spawn_ability(&mut self.rng, Vec2::new(x, y), &mut self.shots);
+            self.ability_timer.0 = 0.0;
+        }
+    }
+
+    // the boss on screen and the life points it started with, read from the actor itself so every
+    // kind of hit shows up
+    pub fn boss(&self) -> Option<(&Actor, f32)> {
+        let gameplay = &self.data.config.gameplay;
+        self.enemies.iter().find_map(|enemy| match enemy.subtag {
+            Subtype::BossEnemy => Some((enemy, gameplay.boss_life_points)),
+            Subtype::TankEnemy => Some((enemy, gameplay.tank_life_points)),
+            _ => None,
+        })
+    }
+
+    // a boss is still flying in from above the screen, once there it heads somewhere else
+    pub fn boss_arriving(&self) -> bool {
+        self.boss().is_some_and(|(boss, _)| {
+            boss.desired_pos == boss_entry()
+                && boss.position.distance(boss_entry()) > BOSS_ARRIVAL_DISTANCE
+        })
+    }
+
+    pub fn is_player_alive(&self) -> bool {
+        !matches!(self.player.tag, TypeActor::None)
+    }
+}
+
+// bottom centre of the screen
+fn respawn_position() -> Vec2 {
+    Vec2::new(screen_size().x / 2.0, screen_size().y - 100.0)
+}
+
+//uses Basic count buff as spawner for all other possible buffs
+fn spawn_ability(rng: &mut impl Rng, source_pos: Vec2, shots: &mut Vec<Actor>) {
+    let position = Vec2::new(source_pos.x, source_pos.y);
+    let desired_pos = Vec2::new(source_pos.x, screen_size().y + 30.0);
+    let shot = create_shot(rng, position, desired_pos, Subtype::BasicCountBuff);
+    shots.push(shot);
+}
@@ -0,0 +1,246 @@
+use std::io::Cursor;
+use std::sync::Arc;
+
+use rodio::{Decoder, OutputStream, OutputStreamHandle, Sink, Source};
+use serde::Deserialize;
+
+use crate::config::AudioConfig;
+use crate::data::{line_of_field, parse_toml, DataError};
+use crate::events::GameEvent;
+
+pub const SOUNDS_FILE: &str = "/sounds.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundEvent {
+    Fire,
+    EnemyHit,
+    Explosion,
+    Pickup,
+    PlayerDamage,
+    BossSpawn,
+}
+
+impl SoundEvent {
+    fn from_name(name: &str) -> Option<SoundEvent> {
+        match name {
+            "fire" => Some(SoundEvent::Fire),
+            "enemy_hit" => Some(SoundEvent::EnemyHit),
+            "explosion" => Some(SoundEvent::Explosion),
+            "pickup" => Some(SoundEvent::Pickup),
+            "player_damage" => Some(SoundEvent::PlayerDamage),
+            "boss_spawn" => Some(SoundEvent::BossSpawn),
+            _ => None,
+        }
+    }
+
+    // the sound a gameplay event makes, a defeated boss also sends EnemyKilled so it explodes once
+    pub fn for_game_event(event: &GameEvent) -> Option<SoundEvent> {
+        match event {
+            GameEvent::ShotFired { .. } => Some(SoundEvent::Fire),
+            GameEvent::ShotHitEnemy { .. } => Some(SoundEvent::EnemyHit),
+            GameEvent::EnemyKilled { .. } | GameEvent::PlayerKilled { .. } => {
+                Some(SoundEvent::Explosion)
+            }
+            GameEvent::PickupCollected { .. } => Some(SoundEvent::Pickup),
+            GameEvent::PlayerDamaged { .. } => Some(SoundEvent::PlayerDamage),
+            GameEvent::BossSpawned { .. } => Some(SoundEvent::BossSpawn),
+            GameEvent::BossDefeated { .. } | GameEvent::WaveStarted { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundDefinition {
+    pub event: SoundEvent,
+    pub file: String,
+    pub volume: f32,
+    pub pitch: f32,
+    pub voices: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSound {
+    event: String,
+    file: String,
+    volume: f32,
+    pitch: f32,
+    voices: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SoundFile {
+    #[serde(default)]
+    sound: Vec<RawSound>,
+}
+
+pub fn parse_sounds(text: &str) -> Result<Vec<SoundDefinition>, DataError> {
+    let file: SoundFile = parse_toml(SOUNDS_FILE, text)?;
+    let error = |index: usize, key: &str, message: String| DataError {
+        file: SOUNDS_FILE.to_string(),
+        line: line_of_field(text, "sound", index, key),
+        message,
+    };
+    let mut sounds: Vec<SoundDefinition> = Vec::new();
+    for (index, raw) in file.sound.into_iter().enumerate() {
+        let event = match SoundEvent::from_name(&raw.event) {
+            Some(event) => event,
+            None => {
+                let message = format!("unknown event '{}'", raw.event);
+                return Err(error(index, "event", message));
+            }
+        };
+        if sounds.iter().any(|sound| sound.event == event) {
+            let message = format!("'{}' already has a sound", raw.event);
+            return Err(error(index, "event", message));
+        }
+        if !(0.0..=1.0).contains(&raw.volume) {
+            let message = "volume must be between 0 and 1".to_string();
+            return Err(error(index, "volume", message));
+        }
+        if raw.pitch <= 0.0 {
+            let message = "pitch must be positive".to_string();
+            return Err(error(index, "pitch", message));
+        }
+        if raw.voices == 0 {
+            let message = "voices must be at least 1".to_string();
+            return Err(error(index, "voices", message));
+        }
+        sounds.push(SoundDefinition {
+            event,
+            file: raw.file,
+            volume: raw.volume,
+            pitch: raw.pitch,
+            voices: raw.voices,
+        });
+    }
+    Ok(sounds)
+}
+
+// drops finished voices, then cuts off the oldest one if all `cap` are still busy
+pub fn make_room<T>(voices: &mut Vec<T>, cap: usize, finished: impl Fn(&T) -> bool) -> Option<T> {
+    voices.retain(|voice| !finished(voice));
+    if voices.len() >= cap {
+        Some(voices.remove(0))
+    } else {
+        None
+    }
+}
+
+struct Sound {
+    definition: SoundDefinition,
+    data: Arc<[u8]>,
+    voices: Vec<Sink>,
+}
+
+pub struct SoundManager {
+    // None without an audio device, the game then simply runs silent
+    output: Option<(OutputStream, OutputStreamHandle)>,
+    sounds: Vec<Sound>,
+    pub settings: AudioConfig,
+}
+
+impl SoundManager {
+    // `files` are the bytes of every definition's file, in the same order
+    pub fn new(
+        definitions: Vec<SoundDefinition>,
+        files: Vec<Vec<u8>>,
+        settings: AudioConfig,
+    ) -> Result<SoundManager, DataError> {
+        let output = match OutputStream::try_default() {
+            Ok(output) => Some(output),
+            Err(e) => {
+                eprintln!("no audio device ({}), sound is off", e);
+                None
+            }
+        };
+        SoundManager::with_output(output, definitions, files, settings)
+    }
+
+    pub fn with_output(
+        output: Option<(OutputStream, OutputStreamHandle)>,
+        definitions: Vec<SoundDefinition>,
+        files: Vec<Vec<u8>>,
+        settings: AudioConfig,
+    ) -> Result<SoundManager, DataError> {
+        let mut sounds = Vec::new();
+        for (definition, bytes) in definitions.into_iter().zip(files) {
+            let data: Arc<[u8]> = bytes.into();
+            // a broken file is reported at startup, not the first time it should play
+            if let Err(e) = Decoder::new(Cursor::new(data.clone())) {
+                return Err(DataError {
+                    file: definition.file.clone(),
+                    line: None,
+                    message: e.to_string(),
+                });
+            }
+            sounds.push(Sound {
+                definition,
+                data,
+                voices: Vec::new(),
+            });
+        }
+        Ok(SoundManager {
+            output,
+            sounds,
+            settings,
+        })
+    }
+
+    // what a sound of the given volume is played at with the current settings
+    pub fn volume_for(&self, volume: f32) -> f32 {
+        if self.settings.muted {
+            0.0
+        } else {
+            volume * self.settings.master_volume * self.settings.sfx_volume
+        }
+    }
+
+    pub fn play(&mut self, event: SoundEvent) {
+        let Some(index) = self
+            .sounds
+            .iter()
+            .position(|sound| sound.definition.event == event)
+        else {
+            return;
+        };
+        let volume = self.volume_for(self.sounds[index].definition.volume);
+        let Some((_, handle)) = &self.output else {
+            return;
+        };
+        if volume <= 0.0 {
+            return;
+        }
+        let sound = &mut self.sounds[index];
+        if let Some(oldest) = make_room(&mut sound.voices, sound.definition.voices, Sink::empty) {
+            oldest.stop();
+        }
+        let (Ok(sink), Ok(decoder)) = (
+            Sink::try_new(handle),
+            Decoder::new(Cursor::new(sound.data.clone())),
+        ) else {
+            return;
+        };
+        sink.set_volume(volume);
+        sink.append(decoder.speed(sound.definition.pitch));
+        sound.voices.push(sink);
+    }
+
+    pub fn play_events(&mut self, events: &[GameEvent]) {
+        for event in events {
+            if let Some(sound) = SoundEvent::for_game_event(event) {
+                self.play(sound);
+            }
+        }
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.settings.muted = !self.settings.muted;
+        for sound in &self.sounds {
+            for voice in &sound.voices {
+                voice.set_volume(self.volume_for(sound.definition.volume));
+            }
+        }
+    }
+}
@@ -1,436 +1,142 @@
-use std::time::Duration;
+use std::io::Read;
 
-//use ggez::audio::SoundSource;
+use ggez::event::{Axis, Button, GamepadId, MouseButton};
 use ggez::glam::Vec2;
-use ggez::input::keyboard::KeyCode;
 use ggez::{graphics, timer, Context, GameError, GameResult};
-use rand::Rng;
 
-use crate::actors::{
-    create_boss, create_enemy, create_player, create_shot, Actor, Subtype, TypeActor,
-};
-use crate::animation::Animation;
 use crate::assets::Assets;
-use crate::behaviour::{
-    basic_enemy_behaviour, boss_enemy_behaviour, update_basic_enemy_movement,
-    update_player_position, update_shot_movement,
-};
-use crate::helpers::{clamp_player, make_rand_pos, random_offscreen_position};
-use crate::SCREEN_SIZE;
-
-use ggez::graphics::Text;
-pub struct InputState {
-    pub velocity: Vec2,
-    is_firing: bool,
-    firing_cooldown: (f32, f32),
-    count_of_weapons: f32,
-}
-
-impl Default for InputState {
-    fn default() -> Self {
-        InputState {
-            velocity: Vec2::new(0.0, 0.0),
-            is_firing: false,
-            firing_cooldown: (0.5, 0.8),
-            count_of_weapons: 1.0,
-        }
-    }
-}
-fn draw_scoreboard(current_score: f32, canvas: &mut graphics::Canvas) {
-    let rounded_score = (current_score * 100.0).round() / 100.0;
-    let score_text = Text::new(format!("Score: {}", rounded_score));
-    let position = Vec2::new(SCREEN_SIZE.x / 2.0 - 50.0, 10.0);
-
-    let drawparams = graphics::DrawParam::new()
-        .dest(position)
-        .scale(Vec2::new(2.0, 2.0));
-    canvas.draw(&score_text, drawparams);
-}
-fn draw_ui_element(image: &graphics::Image, canvas: &mut graphics::Canvas, index: i32) {
-    let drawparams = graphics::DrawParam::new()
-        .dest(Vec2::new(10.0 + (((index - 1) as f32) * 75.0), 10.0))
-        .scale(Vec2::new(5.5, 5.5));
-    canvas.draw(image, drawparams);
-}
-fn draw_actor(
-    assets: &mut Assets,
-    canvas: &mut graphics::Canvas,
-    actor: &Actor,
-    coords: Vec2,
-    rotation: f32,
-) {
-    let image = assets.actor_image(actor);
-    let drawparams = graphics::DrawParam::new()
-        .dest(coords + 0.5)
-        .scale(Vec2::new(5.5, 5.5))
-        .rotation(rotation)
-        .offset(Vec2::new(0.5, 0.5));
-    canvas.draw(image, drawparams);
-}
+use crate::config::{AimMode, AudioConfig, GameConfig};
+use crate::controls::{Action, KeyBindings, KEY_BINDINGS_FILE};
+use crate::data::GameData;
+use crate::formation::GameMode;
+use crate::gamepad::{GamepadInput, Stick};
+use crate::highscores::{HighScoreTable, HIGH_SCORES_FILE};
+use crate::replay::Replay;
+use crate::scenes::{Playing, Scene, SceneStack, Shared, Title, Transition};
+use crate::simulation::UPDATES_PER_SECOND;
+use crate::sound::{parse_sounds, SoundManager, SOUNDS_FILE};
+use crate::LaunchOptions;
+
+// catching up on more than this in one frame would leave the player no time to react
+const MAX_STEPS_PER_FRAME: u32 = 6;
+
+// routes window and device events to the scene on top of the stack
 pub struct State {
-    player: Actor,
-    enemies: Vec<Actor>,
-    shots: Vec<Actor>,
-    assets: Assets,
-    input: InputState,
-    enemy_timer: (f32, f32),
-    enemy_speed: f32,
-    equipped_shields: i32,
-    current_score: f32,
-    is_boss_present: bool,
-    animations: Vec<Animation>,
-    paused: bool,
-    ability_timer: (f32, f32),
-    asteroid_timer: (f32, f32),
+    stack: SceneStack,
+    shared: Shared,
+    gamepad: GamepadInput,
+    // the pad that sent the last event
+    active_gamepad: Option<GamepadId>,
 }
 
 impl State {
-    pub fn new(ctx: &mut Context) -> GameResult<State> {
-        let player = create_player();
-        let assets = Assets::new(ctx)?;
-        let input = InputState::default();
-        let enemies = Vec::new();
-        let shots = Vec::new();
-        let enemy_timer = (0.0, 4.0);
-        let equipped_shields = 0;
-        let current_score = 0.0;
-        let is_boss_present = false;
-        let enemy_speed = 7.0;
-        let animations = Vec::new();
-        let ability_timer = (0.0, 5.0);
-        let asteroid_timer = (0.0, 9.0);
-        Ok(State {
-            player,
-            enemies,
-            shots,
+    // everything is loaded here once, the scenes only borrow it
+    pub fn new(ctx: &mut Context, options: LaunchOptions, config: GameConfig) -> GameResult<State> {
+        let data = load_game_data(ctx)?.with_config(config);
+        let assets = Assets::new(ctx, &data.enemies, &data.weapons)?;
+        let sounds = load_sounds(ctx, data.config.audio.clone())?;
+        let replay = match &options.replay {
+            Some(path) => {
+                Some(Replay::load(path).map_err(|e| GameError::CustomError(e.to_string()))?)
+            }
+            None => None,
+        };
+        let high_score_path = ctx.fs.user_data_dir().join(HIGH_SCORES_FILE);
+        let high_scores = HighScoreTable::load(&high_score_path);
+        let bindings_path = ctx.fs.user_data_dir().join(KEY_BINDINGS_FILE);
+        let bindings = KeyBindings::load(&bindings_path, &data.config.controls.layout);
+        let gamepad = GamepadInput::new(data.config.controls.stick_deadzone);
+        let mut shared = Shared {
+            options,
+            data,
             assets,
-            input,
-            enemy_speed,
-            equipped_shields,
-            current_score,
-            is_boss_present,
-            animations,
-            paused: false,
-            enemy_timer,
-            ability_timer,
-            asteroid_timer,
+            sounds,
+            bindings,
+            bindings_path,
+            high_scores,
+            high_score_path,
+            replay,
+            mode: GameMode::Survival,
+        };
+        // a replay is watched straight away
+        let first: Box<dyn Scene> = match shared.replay {
+            Some(_) => Box::new(Playing::new(&mut shared)),
+            None => Box::new(Title::new()),
+        };
+        Ok(State {
+            stack: SceneStack::new(first),
+            shared,
+            gamepad,
+            active_gamepad: None,
         })
     }
 
-    fn spawn_enemy(&mut self, dt: f32) {
-        self.enemy_timer.0 += dt;
-        if self.enemy_timer.0 >= self.enemy_timer.1 {
-            let destination = make_rand_pos();
-            let enemy = create_enemy(destination, self.enemy_speed, self.current_score);
-            self.enemies.push(enemy);
-            self.enemy_timer.0 = 0.0;
-            //difficulty enhancer
-            if self.enemy_timer.1 >= 1.5 {
-                self.enemy_timer.1 -= 0.1;
-            }
-            self.enemy_speed += 0.04;
+    fn apply(&mut self, ctx: &mut Context, transition: Transition) {
+        if !self.stack.apply(transition) {
+            ctx.request_quit();
         }
     }
 
-    fn spawn_asteroid(&mut self, dt: f32) {
-        self.enemy_timer.0 += dt;
-        if self.enemy_timer.0 >= self.enemy_timer.1 {
-            let source_pos = random_offscreen_position(SCREEN_SIZE.x, SCREEN_SIZE.y);
-            let destination = random_offscreen_position(SCREEN_SIZE.x, SCREEN_SIZE.y);
-            let shot = create_shot(source_pos, destination, Subtype::AsteroidShot);
-            self.shots.push(shot);
-            self.asteroid_timer.0 = 0.0;
-        }
-    }
-    fn spawn_player_shot(&mut self, dt: f32) {
-        self.input.firing_cooldown.0 += dt;
-        if self.input.is_firing && self.input.firing_cooldown.0 >= self.input.firing_cooldown.1 {
-            let mut dest_x = self.player.position.x;
-            for i in 0..(self.input.count_of_weapons as i32) {
-                if i % 2 == 0 {
-                    dest_x += (i as f32) * SCREEN_SIZE.x / 4.0;
-                } else {
-                    dest_x -= (i as f32) * SCREEN_SIZE.x / 4.0;
-                }
-                let source_pos = Vec2::new(self.player.position.x, self.player.position.y - 2.0);
-                let dest_pos = Vec2::new(dest_x, -100.0);
-                let shot = create_shot(source_pos, dest_pos, Subtype::BasicShot);
-                self.shots.push(shot);
-                self.input.firing_cooldown.0 = 0.0;
+    // keyboard, mouse and gamepad all end up here
+    fn action_down(&mut self, ctx: &mut Context, action: Action, repeated: bool) {
+        if action == Action::Mute {
+            if !repeated {
+                self.shared.sounds.toggle_mute();
             }
+            return;
         }
+        let transition = self
+            .stack
+            .top()
+            .action_down(&mut self.shared, action, repeated);
+        self.apply(ctx, transition);
     }
 
-    fn handle_collision(&mut self, ctx: &mut Context) {
-        for shot in &mut self.shots {
-            let distance_to_player = self.player.position - shot.position;
-            if distance_to_player.length() < self.player.box_size + shot.box_size {
-                match shot.subtag {
-                    Subtype::EnemyShot | Subtype::AsteroidShot => {
-                        shot.life_points = 0.0;
-                        self.player.life_points -= 1.0;
-                        self.equipped_shields -= 1;
-                        if self.player.life_points <= 0.0 {
-                            self.animations.push(
-                                Animation::new(
-                                    ctx,
-                                    "/explosion_sheet.png",
-                                    4,
-                                    Duration::from_millis(100),
-                                    self.player.position,
-                                )
-                                .unwrap(),
-                            );
-                            self.player = Actor::new();
-                            self.paused = true;
-                        }
-                    }
-                    _ => (),
-                }
-                match shot.subtag {
-                    Subtype::BasicCountBuff => {
-                        self.input.count_of_weapons += 1.0;
-                        shot.life_points = 0.0;
-                    }
-                    Subtype::BasicReloadBuff => {
-                        self.input.firing_cooldown.1 -= 0.02;
-                        shot.life_points = 0.0;
-                    }
-                    Subtype::BasicShieldBuff => {
-                        if self.player.life_points < 4.0 {
-                            self.player.life_points += 1.0;
-                            self.equipped_shields += 1;
-                        }
-                        shot.life_points = 0.0;
-                    }
-                    _ => {}
-                }
-            }
-
-            for enemy in &mut self.enemies {
-                let distance_to_enemy = enemy.position - shot.position;
-                if distance_to_enemy.length() < enemy.box_size + shot.box_size {
-                    match shot.subtag {
-                        Subtype::BasicShot | Subtype::AsteroidShot => {
-                            match enemy.subtag {
-                                Subtype::BasicEnemy | Subtype::KamikazeEnemy => {
-                                    enemy.life_points -= 1.0;
-                                }
-                                Subtype::BossEnemy => {
-                                    shot.life_points = 0.0;
-                                    enemy.life_points -= 3.0;
-                                }
-                                _ => (),
-                            }
-                            if let Subtype::BasicShot = shot.subtag {
-                                shot.life_points = 0.0;
-                            }
-                            self.animations.push(
-                                Animation::new(
-                                    ctx,
-                                    "/explosion_sheet.png",
-                                    4,
-                                    Duration::from_millis(100),
-                                    enemy.position,
-                                )
-                                .unwrap(),
-                            );
-                        }
-                        _ => (),
-                    }
-                }
-            }
-        }
-
-        for enemy in &mut self.enemies {
-            //player to enemy collision handle script
-            let distance_to_player = self.player.position - enemy.position;
-            if distance_to_player.length() < enemy.box_size + self.player.box_size {
-                match enemy.subtag {
-                    Subtype::KamikazeEnemy | Subtype::BasicEnemy => {
-                        enemy.life_points = 0.0;
-                        self.animations.push(
-                            Animation::new(
-                                ctx,
-                                "/explosion_sheet.png",
-                                4,
-                                Duration::from_millis(100),
-                                enemy.position,
-                            )
-                            .unwrap(),
-                        );
-                    }
-                    _ => (),
-                }
-                self.player.life_points -= 1.0;
-                self.equipped_shields -= 1;
-                if self.player.life_points <= 0.0 {
-                    self.animations.push(
-                        Animation::new(
-                            ctx,
-                            "/explosion_sheet.png",
-                            4,
-                            Duration::from_millis(100),
-                            self.player.position,
-                        )
-                        .unwrap(),
-                    );
-                }
-            }
-
-            //Shot collision handle script
-            for i in 0..self.shots.len() {
-                let distance_to_player = self.player.position - self.shots[i].position;
-                if distance_to_player.length() < self.player.box_size + self.shots[i].box_size {
-                    if let Subtype::EnemyShot = self.shots[i].subtag {
-                        self.player.life_points -= 1.0;
-                        self.equipped_shields -= 1;
-                        self.shots[i].life_points = 0.0;
-                        self.animations.push(
-                            Animation::new(
-                                ctx,
-                                "/explosion_sheet.png",
-                                4,
-                                Duration::from_millis(100),
-                                enemy.position,
-                            )
-                            .unwrap(),
-                        );
-                    }
-                }
-
-                let distance_to_enemy = enemy.position - self.shots[i].position;
-                if distance_to_enemy.length() < enemy.box_size + self.shots[i].box_size {
-                    if let Subtype::BasicShot = self.shots[i].subtag {
-                        enemy.life_points -= 1.0;
-                        self.shots[i].life_points = 0.0;
-                        //sets boss bool to false if boss is dead
-                        if enemy.life_points <= -1.0 {
-                            match enemy.subtag {
-                                Subtype::BossEnemy /*| Subtype::TankEnemy*/ => {
-                                    self.is_boss_present = false;
-                                }
-                                Subtype::BasicEnemy | Subtype::KamikazeEnemy => {
-                                    self.animations.push(
-                                        Animation::new(
-                                            ctx,
-                                            "/explosion_sheet.png",
-                                            4,
-                                            Duration::from_millis(100),
-                                            enemy.position,
-                                        )
-                                        .unwrap(),
-                                    );
-                                }
-                                _ => (),
-                            }
-                        }
-                    }
-                }
-            }
-            if self.player.life_points <= 0.0 {
-                self.player = Actor::new();
-                self.paused = true;
-            }
-        }
-        self.handle_life_state();
-    }
-
-    //could optimise this, but no need at current moment
-    fn handle_life_state(&mut self) {
-        let predicate = |actor: &Actor| actor.life_points > 0.0;
-        self.shots.retain(&predicate);
-        self.enemies.retain(&predicate);
+    fn action_up(&mut self, action: Action) {
+        self.stack.top().action_up(action);
     }
 
-    fn trigger_enemy_ability(&mut self, dt: f32) {
-        for enemy in &mut self.enemies {
-            match enemy.subtag {
-                Subtype::BasicEnemy => {
-                    basic_enemy_behaviour(enemy, &mut self.player, &mut self.shots);
-                }
-                Subtype::KamikazeEnemy => {
-                    enemy.desired_pos = self.player.position;
-                }
-                Subtype::BossEnemy => {
-                    boss_enemy_behaviour(enemy, &mut self.player, &mut self.shots, dt);
-                }
-                _ => (),
-            }
-        }
+    // the run on top decides how mouse and right stick are read, menus go by the settings
+    fn aim_mode(&mut self) -> AimMode {
+        self.stack
+            .top()
+            .aim_mode()
+            .unwrap_or(self.shared.data.config.controls.aim)
     }
 
-    fn spawn_boss(&mut self) {
-        if ((self.current_score.ceil() % 40.0) == 0.0) & (!self.is_boss_present) {
-            self.is_boss_present = true;
-            let boss = create_boss(Subtype::BossEnemy);
-            self.enemies.push(boss);
-            self.enemy_timer.1 += 1.5;
+    // ggez doesn't report unplugged pads, so the last one used is looked for every update
+    fn check_gamepad(&mut self, ctx: &mut Context) {
+        let Some(id) = self.active_gamepad else {
+            return;
+        };
+        if ctx.gamepad.gamepads().any(|(connected, _)| connected == id) {
+            return;
         }
-    }
-
-    fn animation_handler(&mut self) {
-        self.animations.retain_mut(|animation| {
-            animation.update(Duration::from_millis(16));
-            !animation.finished
-        });
-    }
-
-    fn spawn_behaviour(&mut self, dt: f32) {
-        self.ability_timer.0 += dt;
-        if self.ability_timer.0 >= self.ability_timer.1 {
-            let mut rng = rand::thread_rng();
-            let x = rng.gen_range(5.0..SCREEN_SIZE.x);
-            let y = -10.0;
-            spawn_ability(Vec2::new(x, y), &mut self.shots);
-            self.ability_timer.0 = 0.0;
+        self.active_gamepad = None;
+        let scene = self.stack.top();
+        for action in self.gamepad.disconnect() {
+            scene.action_up(action);
         }
+        scene.set_stick(Vec2::ZERO);
+        let transition = scene.interrupted();
+        self.apply(ctx, transition);
     }
 }
 
-//uses Basic count buff as spawner for all other possible buffs
-fn spawn_ability(source_pos: Vec2, shots: &mut Vec<Actor>) {
-    let position = Vec2::new(source_pos.x, source_pos.y);
-    let desired_pos = Vec2::new(source_pos.x, SCREEN_SIZE.y + 30.0);
-    let shot = create_shot(position, desired_pos, Subtype::BasicCountBuff);
-    shots.push(shot);
-}
-
 impl ggez::event::EventHandler<GameError> for State {
     fn update(&mut self, ctx: &mut Context) -> Result<(), GameError> {
-        if self.paused {
-            return Ok(());
+        self.check_gamepad(ctx);
+        let mut steps = 0;
+        while ctx.time.check_update_time(UPDATES_PER_SECOND) {
+            // after a stall the time that's left is skipped rather than simulated all at once
+            if steps == MAX_STEPS_PER_FRAME {
+                while ctx.time.check_update_time(UPDATES_PER_SECOND) {}
+                break;
+            }
+            let transition = self.stack.top().update(&mut self.shared);
+            self.apply(ctx, transition);
+            steps += 1;
         }
-
-        let delta_time = ctx.time.delta().as_secs_f32().min(0.1);
-        //Handles player movement
-        update_player_position(&mut self.player, &mut self.input, delta_time);
-
-        //clamps player to screen size
-        clamp_player(&mut self.player);
-
-        //Basic Enemy spawn script
-        self.spawn_enemy(delta_time);
-        update_basic_enemy_movement(&mut self.enemies, delta_time);
-        self.trigger_enemy_ability(delta_time);
-
-        //Player shot spawn
-        self.spawn_player_shot(delta_time);
-        update_shot_movement(&mut self.shots, delta_time);
-
-        self.spawn_asteroid(delta_time);
-
-        //Handles collision events
-        self.handle_collision(ctx);
-
-        self.current_score += delta_time;
-
-        // Boss Spawn script
-        self.spawn_boss();
-
-        self.animation_handler();
-
-        self.spawn_behaviour(delta_time);
         Ok(())
     }
 
@@ -438,38 +144,9 @@ impl ggez::event::EventHandler<GameError> for State {
         let mut canvas =
             graphics::Canvas::from_frame(ctx, graphics::Color::from([0.1, 0.2, 0.3, 1.0]));
         canvas.set_sampler(graphics::Sampler::nearest_clamp());
-        {
-            let assets = &mut self.assets;
-            let player = &mut self.player;
-            draw_actor(
-                assets,
-                &mut canvas,
-                player,
-                player.position,
-                player.rotation,
-            );
-            for enemy in &mut self.enemies {
-                draw_actor(assets, &mut canvas, enemy, enemy.position, enemy.rotation);
-            }
-            for shot in &mut self.shots {
-                draw_actor(assets, &mut canvas, shot, shot.position, shot.rotation);
-            }
-            // drawing shield (if any are picked up at all)
-            for i in 1..self.equipped_shields + 1 {
-                let image = assets.shield_ui_sprite.clone();
-                draw_ui_element(&image, &mut canvas, i);
-            }
-            draw_scoreboard(self.current_score, &mut canvas);
-
-            for animation in &mut self.animations {
-                animation.draw(ctx, &mut canvas);
-            }
-
-            if self.paused {
-                draw_game_over_screen(&mut canvas);
-            }
+        for scene in self.stack.visible() {
+            scene.draw(ctx, &mut self.shared, &mut canvas);
         }
-
         canvas.finish(ctx)?;
 
         timer::yield_now();
@@ -479,25 +156,13 @@ impl ggez::event::EventHandler<GameError> for State {
         &mut self,
         ctx: &mut Context,
         input: ggez::input::keyboard::KeyInput,
-        _repeated: bool,
+        repeated: bool,
     ) -> GameResult {
-        let speed = 600.0;
-        match input.keycode {
-            Some(KeyCode::Right) => self.input.velocity.x = speed,
-            Some(KeyCode::Left) => self.input.velocity.x = -speed,
-            Some(KeyCode::Down) => self.input.velocity.y = speed,
-            Some(KeyCode::Up) => self.input.velocity.y = -speed,
-            Some(KeyCode::R) => {
-                if let TypeActor::None = self.player.tag {
-                    *self = State::new(ctx).unwrap()
-                }
-            }
-            Some(KeyCode::Space) => {
-                self.input.is_firing = true;
-                // It can play a sound - but let's not do that :)
-                //self.assets.shoot_sound.play_later();
-            }
-            _ => (),
+        if let Some(action) = input
+            .keycode
+            .and_then(|key| self.shared.bindings.action(key))
+        {
+            self.action_down(ctx, action, repeated);
         }
         Ok(())
     }
@@ -507,21 +172,136 @@ impl ggez::event::EventHandler<GameError> for State {
         _ctx: &mut Context,
         input: ggez::input::keyboard::KeyInput,
     ) -> GameResult {
-        match input.keycode {
-            Some(KeyCode::Right) | Some(KeyCode::Left) => self.input.velocity.x = 0.0,
-            Some(KeyCode::Up) | Some(KeyCode::Down) => self.input.velocity.y = 0.0,
-            Some(KeyCode::Space) => self.input.is_firing = false,
+        if let Some(action) = input
+            .keycode
+            .and_then(|key| self.shared.bindings.action(key))
+        {
+            self.action_up(action);
+        }
+        Ok(())
+    }
+
+    fn gamepad_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: Button,
+        id: GamepadId,
+    ) -> GameResult {
+        self.active_gamepad = Some(id);
+        if let Some(action) = self.gamepad.button_down(button) {
+            self.action_down(ctx, action, false);
+        }
+        Ok(())
+    }
+
+    fn gamepad_button_up_event(
+        &mut self,
+        _ctx: &mut Context,
+        button: Button,
+        id: GamepadId,
+    ) -> GameResult {
+        self.active_gamepad = Some(id);
+        if let Some(action) = self.gamepad.button_up(button) {
+            self.action_up(action);
+        }
+        Ok(())
+    }
+
+    fn gamepad_axis_event(
+        &mut self,
+        _ctx: &mut Context,
+        axis: Axis,
+        value: f32,
+        id: GamepadId,
+    ) -> GameResult {
+        self.active_gamepad = Some(id);
+        match self.gamepad.axis(axis, value) {
+            Some(Stick::Left) => self.stack.top().set_stick(self.gamepad.stick()),
+            // letting go of the right stick keeps the last aim
+            Some(Stick::Right) if self.aim_mode() == AimMode::Stick => {
+                if let Some(aim) = self.gamepad.aim() {
+                    self.stack.top().set_aim(aim);
+                }
+            }
             _ => (),
         }
         Ok(())
     }
+
+    fn mouse_motion_event(
+        &mut self,
+        _ctx: &mut Context,
+        x: f32,
+        y: f32,
+        _dx: f32,
+        _dy: f32,
+    ) -> GameResult {
+        if self.aim_mode() == AimMode::Mouse {
+            self.stack.top().set_aim(Vec2::new(x, y));
+        }
+        Ok(())
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) -> GameResult {
+        if let (MouseButton::Left, AimMode::Mouse) = (button, self.aim_mode()) {
+            self.action_down(ctx, Action::Fire, false);
+        }
+        Ok(())
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        _ctx: &mut Context,
+        button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) -> GameResult {
+        if let (MouseButton::Left, AimMode::Mouse) = (button, self.aim_mode()) {
+            self.action_up(Action::Fire);
+        }
+        Ok(())
+    }
+
+    fn focus_event(&mut self, ctx: &mut Context, gained: bool) -> GameResult {
+        if !gained {
+            let transition = self.stack.top().interrupted();
+            self.apply(ctx, transition);
+        }
+        Ok(())
+    }
+
+    fn quit_event(&mut self, _ctx: &mut Context) -> GameResult<bool> {
+        self.stack.leave_all();
+        Ok(false)
+    }
+}
+
+fn read_resource(ctx: &Context, path: &str) -> GameResult<String> {
+    let mut text = String::new();
+    ctx.fs.open(path)?.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+// read at startup so designers can edit enemies, waves and weapons without recompiling
+fn load_game_data(ctx: &Context) -> GameResult<GameData> {
+    Ok(GameData::load(|file| {
+        read_resource(ctx, file).map_err(|e| e.to_string())
+    })?)
 }
 
-fn draw_game_over_screen(canvas: &mut graphics::Canvas) {
-    let score_text = Text::new("GAME OVER");
-    let position = SCREEN_SIZE / 2.0 - 150.0;
-    let drawparams = graphics::DrawParam::new()
-        .dest(position)
-        .scale(Vec2::new(5.0, 5.0));
-    canvas.draw(&score_text, drawparams);
+fn load_sounds(ctx: &Context, settings: AudioConfig) -> GameResult<SoundManager> {
+    let definitions = parse_sounds(&read_resource(ctx, SOUNDS_FILE)?)?;
+    let mut files = Vec::new();
+    for definition in &definitions {
+        let mut bytes = Vec::new();
+        ctx.fs.open(&definition.file)?.read_to_end(&mut bytes)?;
+        files.push(bytes);
+    }
+    Ok(SoundManager::new(definitions, files, settings)?)
 }
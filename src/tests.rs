@@ -1,11 +1,42 @@
 #[cfg(test)]
 mod test {
-    use crate::actors::{Actor, TypeActor};
+    use crate::actors::{create_boss, create_enemy, create_shot, Actor, Subtype, TypeActor};
+    use crate::behaviour::{
+        kamikaze_behaviour, tank_enemy_behaviour, update_shot_movement, TANK_FIRE_INTERVAL,
+        TANK_SPREAD_SHOTS,
+    };
+    use crate::bunkers::{Bunker, BUNKER_HEIGHT, BUNKER_PIXEL, BUNKER_WIDTH};
+    use crate::config::{AimMode, AudioConfig, GameConfig};
+    use crate::controls::{Action, KeyBindings};
+    use crate::data::GameData;
+    use crate::enemies::{BehaviourKind, EnemyCatalog, Steering};
+    use crate::events::GameEvent;
+    use crate::formation::{
+        front_row, is_invader, Formation, GameMode, FORMATION_COLUMNS, FORMATION_ROWS,
+    };
+    use crate::gamepad::{GamepadInput, Stick};
+    use crate::grid::SpatialGrid;
     use crate::helpers::{
         clamp_player, make_rand_pos, position_to_direction, random_offscreen_position,
     };
-    use crate::SCREEN_SIZE;
+    use crate::highscores::{HighScoreEntry, HighScoreTable, MAX_HIGH_SCORES};
+    use crate::patterns::Pattern;
+    use crate::replay::{run_replay, Replay, ReplayPlayer};
+    use crate::scenes::{Menu, MenuChoice, Playing, Scene, SceneStack, Shared, Transition};
+    use crate::simulation::{InputState, Simulation, FIXED_DT};
+    use crate::sound::{make_room, parse_sounds, SoundEvent, SoundManager};
+    use crate::waves::{parse_waves, WaveDirector, WavePhase};
+    use crate::weapons::{Arsenal, WeaponCatalog, WeaponKind};
+    use crate::{parse_options, screen_size, LaunchOptions};
+    use ggez::event::{Axis, Button};
     use ggez::glam::Vec2;
+    use ggez::input::keyboard::KeyCode;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::cell::RefCell;
+    use std::path::PathBuf;
+    use std::rc::Rc;
+    use std::time::Instant;
 
     #[test]
     fn test_position_to_direction() {
@@ -18,15 +49,17 @@ mod test {
 
     #[test]
     fn test_make_rand_pos() {
-        let pos = make_rand_pos();
-        assert!(pos.x >= 5.0 && pos.x <= SCREEN_SIZE.x);
-        assert!(pos.y >= 0.0 && pos.y <= SCREEN_SIZE.y / 2.0);
+        let mut rng = StdRng::seed_from_u64(7);
+        let pos = make_rand_pos(&mut rng);
+        assert!(pos.x >= 5.0 && pos.x <= screen_size().x);
+        assert!(pos.y >= 0.0 && pos.y <= screen_size().y / 2.0);
     }
 
     #[test]
     fn test_clamp_player() {
         let mut player = Actor {
-            position: Vec2::new(-10.0, SCREEN_SIZE.y + 10.0),
+            position: Vec2::new(-10.0, screen_size().y + 10.0),
+            previous_position: Vec2::new(-10.0, screen_size().y + 10.0),
             tag: TypeActor::Player,
             subtag: crate::actors::Subtype::BasicEnemy,
             velocity: Vec2::new(10.0, 10.0),
@@ -34,20 +67,1539 @@ mod test {
             life_points: 0.0,
             box_size: 0.0,
             rotation: 0.0,
+            archetype: 0,
+            damage: 0.0,
+            speed_bonus: 0.0,
+            bullet: None,
         };
         clamp_player(&mut player);
-        assert!(player.position.x >= 0.0 && player.position.x <= SCREEN_SIZE.x - 4.0);
-        assert!(player.position.y >= 0.0 && player.position.y <= SCREEN_SIZE.y - 4.0);
+        assert!(player.position.x >= 0.0 && player.position.x <= screen_size().x - 4.0);
+        assert!(player.position.y >= 0.0 && player.position.y <= screen_size().y - 4.0);
     }
 
     #[test]
     fn test_random_offscreen_position() {
-        let screen_width = SCREEN_SIZE.x;
-        let screen_height = SCREEN_SIZE.y;
-        let pos = random_offscreen_position(screen_width, screen_height);
+        let screen_width = screen_size().x;
+        let screen_height = screen_size().y;
+        let mut rng = StdRng::seed_from_u64(7);
+        let pos = random_offscreen_position(&mut rng, screen_width, screen_height);
 
         let is_offscreen =
             pos.x < 0.0 || pos.x > screen_width || pos.y < 0.0 || pos.y > screen_height;
         assert!(is_offscreen);
     }
+
+    #[test]
+    fn test_simulation_runs_headless() {
+        let mut simulation = Simulation::new(1, GameData::default());
+        simulation.input.is_firing = true;
+        for _ in 0..60 * 30 {
+            simulation.step();
+            if simulation.game_over {
+                break;
+            }
+        }
+        assert!(simulation.current_score > 0.0);
+        if !simulation.game_over {
+            let position = simulation.player.position;
+            assert!(position.x >= 0.0 && position.x <= screen_size().x);
+            assert!(position.y >= 0.0 && position.y <= screen_size().y);
+        }
+    }
+
+    #[test]
+    fn test_simulation_emits_events_on_ram() {
+        let mut simulation = Simulation::new(1, GameData::default());
+        simulation.lives = 1;
+        let player_position = simulation.player.position;
+        let catalog = GameData::default().enemies;
+        let mut enemy = create_enemy(&catalog, 1, player_position, player_position, 0.0, 0.0);
+        enemy.position = player_position;
+        simulation.enemies.push(enemy);
+        simulation.step();
+
+        assert!(simulation.enemies.is_empty());
+        assert!(simulation.game_over);
+        assert!(simulation.events.contains(&GameEvent::EnemyKilled {
+            subtype: Subtype::KamikazeEnemy,
+            position: player_position,
+        }));
+        assert!(simulation.events.contains(&GameEvent::PlayerKilled {
+            position: player_position
+        }));
+        assert_eq!(simulation.statistics.enemies_killed, 1);
+        assert_eq!(simulation.statistics.damage_taken, 1);
+    }
+
+    #[test]
+    fn test_enemy_shot_down_while_ramming() {
+        let mut simulation = Simulation::new(1, GameData::default());
+        let position = simulation.player.position;
+        let catalog = GameData::default().enemies;
+        let mut enemy = create_enemy(&catalog, 1, position, position, 0.0, 0.0);
+        enemy.life_points = 1.0;
+        simulation.enemies.push(enemy);
+        let mut rng = StdRng::seed_from_u64(1);
+        let target = position - Vec2::new(0.0, 100.0);
+        simulation.shots = vec![create_shot(&mut rng, position, target, Subtype::BasicShot)];
+        simulation.handle_collision();
+
+        // the shot got it first, so it's killed once and no longer hurts the player
+        let kills = simulation
+            .events
+            .iter()
+            .filter(|event| matches!(event, GameEvent::EnemyKilled { .. }))
+            .count();
+        assert_eq!(kills, 1);
+        assert!(!simulation
+            .events
+            .iter()
+            .any(|event| matches!(event, GameEvent::PlayerDamaged { .. })));
+        assert!(simulation.enemies.is_empty());
+    }
+
+    #[test]
+    fn test_lives_and_invulnerability() {
+        let mut simulation = Simulation::new(1, GameData::default());
+        assert_eq!(simulation.lives, 3);
+        let catalog = GameData::default().enemies;
+        let ram = |simulation: &mut Simulation| {
+            let position = simulation.player.position;
+            simulation
+                .enemies
+                .push(create_enemy(&catalog, 1, position, position, 0.0, 0.0));
+            simulation.step();
+        };
+
+        ram(&mut simulation);
+        assert_eq!(simulation.lives, 2);
+        assert!(!simulation.game_over);
+        let bottom_centre = Vec2::new(screen_size().x / 2.0, screen_size().y - 100.0);
+        assert_eq!(simulation.player.position, bottom_centre);
+        assert!(simulation.invulnerable > 0.0);
+
+        // blinking, nothing gets through
+        ram(&mut simulation);
+        assert_eq!(simulation.lives, 2);
+        assert_eq!(simulation.statistics.damage_taken, 1);
+
+        // a shield soaks the next hit without costing a life
+        simulation.invulnerable = 0.0;
+        simulation.player.life_points += 1.0;
+        simulation.equipped_shields += 1;
+        ram(&mut simulation);
+        assert_eq!(simulation.lives, 2);
+        assert_eq!(simulation.equipped_shields, 0);
+
+        simulation.invulnerable = 0.0;
+        ram(&mut simulation);
+        simulation.invulnerable = 0.0;
+        ram(&mut simulation);
+        assert_eq!(simulation.lives, 0);
+        assert!(simulation.game_over);
+    }
+
+    #[test]
+    fn test_same_seed_same_run() {
+        let run = |seed: u64| {
+            let mut simulation = Simulation::new(seed, GameData::default());
+            simulation.input.is_firing = true;
+            for _ in 0..60 * 20 {
+                simulation.step();
+            }
+            simulation
+        };
+        let first = run(42);
+        let second = run(42);
+        assert_eq!(first.enemies.len(), second.enemies.len());
+        for (a, b) in first.enemies.iter().zip(&second.enemies) {
+            assert_eq!(a.subtag, b.subtag);
+            assert_eq!(a.position, b.position);
+        }
+        assert_eq!(first.shots.len(), second.shots.len());
+        for (a, b) in first.shots.iter().zip(&second.shots) {
+            assert_eq!(a.subtag, b.subtag);
+            assert_eq!(a.position, b.position);
+        }
+        assert_eq!(first.current_score, second.current_score);
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let args = |list: &[&str]| {
+            list.iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<_>>()
+                .into_iter()
+        };
+        assert_eq!(parse_options(args(&["--seed", "99"])).seed, Some(99));
+        assert_eq!(parse_options(args(&["--seed", "abc"])).seed, None);
+        assert_eq!(parse_options(args(&[])), LaunchOptions::default());
+        let options = parse_options(args(&["--record", "run.json"]));
+        assert_eq!(options.record, Some(PathBuf::from("run.json")));
+        let options = parse_options(args(&["--set", "window.vsync=false", "--set", "a.b=1"]));
+        assert_eq!(options.overrides, vec!["window.vsync=false", "a.b=1"]);
+    }
+
+    #[test]
+    fn test_replay_reproduces_run() {
+        let mut simulation = Simulation::new(5, GameData::default());
+        let mut replay = Replay::new(simulation.seed, GameMode::Survival, AimMode::Fixed);
+        let script = [
+            (0, Action::Fire, true),
+            (30, Action::MoveLeft, true),
+            (90, Action::MoveLeft, false),
+            (120, Action::MoveUp, true),
+            (200, Action::Fire, false),
+        ];
+        for frame in 0..60 * 15 {
+            for (at, action, pressed) in script {
+                if at == frame {
+                    replay.record_action(simulation.frame, action, pressed);
+                    if pressed {
+                        simulation.input.press(action);
+                    } else {
+                        simulation.input.release(action);
+                    }
+                }
+            }
+            replay.record_frame();
+            simulation.step();
+            if simulation.game_over {
+                break;
+            }
+        }
+
+        let loaded = Replay::from_json(&replay.to_json()).unwrap();
+        assert_eq!(loaded, replay);
+        let replayed = run_replay(&loaded, GameData::default());
+        assert_eq!(replayed.current_score, simulation.current_score);
+        assert_eq!(replayed.player.position, simulation.player.position);
+        assert_eq!(replayed.enemies.len(), simulation.enemies.len());
+        assert_eq!(replayed.game_over, simulation.game_over);
+    }
+
+    #[test]
+    fn test_shot_speed_ignores_step_size() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let start = Vec2::new(100.0, 500.0);
+        let target = Vec2::new(100.0, -100.0);
+        let mut small_steps = vec![create_shot(&mut rng, start, target, Subtype::BasicShot)];
+        let mut one_step = vec![create_shot(&mut rng, start, target, Subtype::BasicShot)];
+        update_shot_movement(&mut small_steps, 1.0 / 120.0);
+        update_shot_movement(&mut small_steps, 1.0 / 120.0);
+        update_shot_movement(&mut one_step, 1.0 / 60.0);
+        let difference = small_steps[0].position - one_step[0].position;
+        assert!(difference.length() < 0.001);
+    }
+
+    #[test]
+    fn test_step_keeps_previous_position() {
+        let mut simulation = Simulation::new(1, GameData::default());
+        simulation.input.press(Action::MoveRight);
+        simulation.step();
+        simulation.step();
+        let player = &simulation.player;
+        assert!(player.position.x > player.previous_position.x);
+        let halfway = player.previous_position.lerp(player.position, 0.5);
+        assert!(halfway.x > player.previous_position.x && halfway.x < player.position.x);
+    }
+
+    #[test]
+    fn test_spatial_grid_query() {
+        let mut grid = SpatialGrid::new(64.0);
+        grid.insert(0, Vec2::new(10.0, 10.0), 5.0);
+        grid.insert(1, Vec2::new(63.0, 10.0), 5.0);
+        grid.insert(2, Vec2::new(900.0, 900.0), 5.0);
+        let mut found = Vec::new();
+        grid.query(Vec2::new(40.0, 12.0), 10.0, &mut found);
+        assert_eq!(found, vec![0, 1]);
+        grid.query(Vec2::new(500.0, 500.0), 10.0, &mut found);
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_shot_hits_enemy_once() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut simulation = Simulation::new(2, GameData::default());
+        let position = Vec2::new(300.0, 300.0);
+        let catalog = GameData::default().enemies;
+        let mut enemy = create_enemy(&catalog, 0, position, position, 0.0, 200.0);
+        enemy.position = position;
+        let life_points = enemy.life_points;
+        simulation.enemies.push(enemy);
+        let target = Vec2::new(300.0, -100.0);
+        let shot = create_shot(&mut rng, position, target, Subtype::BasicShot);
+        simulation.shots.push(shot);
+        simulation.handle_collision();
+
+        assert!(simulation.shots.is_empty());
+        assert_eq!(simulation.enemies[0].life_points, life_points - 1.0);
+        assert_eq!(simulation.events.len(), 1);
+    }
+
+    // cargo test --release -- --ignored --nocapture bench_collision_scaling
+    #[test]
+    #[ignore]
+    fn bench_collision_scaling() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let catalog = GameData::default().enemies;
+        let mut timings = Vec::new();
+        for shot_count in [1000, 2000, 4000, 8000] {
+            let mut simulation = Simulation::new(4, GameData::default());
+            for _ in 0..200 {
+                let position = make_rand_pos(&mut rng);
+                simulation
+                    .enemies
+                    .push(create_enemy(&catalog, 0, position, position, 0.0, 10_000.0));
+                simulation.enemies.last_mut().unwrap().position = position;
+            }
+            for i in 0..shot_count {
+                let position = Vec2::new(
+                    (i % 100) as f32 * screen_size().x / 100.0,
+                    screen_size().y * 0.75 + (i / 100) as f32 % 200.0,
+                );
+                let target = Vec2::new(position.x, -100.0);
+                let mut shot = create_shot(&mut rng, position, target, Subtype::BasicShot);
+                shot.box_size = 2.0;
+                simulation.shots.push(shot);
+            }
+            simulation.player.position = Vec2::new(-1000.0, -1000.0);
+            let rounds = 50;
+            let start = Instant::now();
+            for _ in 0..rounds {
+                simulation.handle_collision();
+            }
+            let per_round = start.elapsed() / rounds;
+            println!("{} shots: {:?} per collision pass", shot_count, per_round);
+            timings.push(per_round.as_secs_f64());
+        }
+        // close to linear, 8x the shots should cost well under 16x
+        assert!(timings[3] / timings[0] < 16.0);
+    }
+
+    #[test]
+    fn test_boss_kill_events() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let mut simulation = Simulation::new(6, GameData::default());
+        let mut boss = create_boss(Subtype::BossEnemy, 8.0);
+        boss.position = Vec2::new(600.0, 200.0);
+        boss.life_points = 2.0;
+        simulation.enemies.push(boss);
+        let target = Vec2::new(600.0, -100.0);
+        let shot = create_shot(
+            &mut rng,
+            Vec2::new(600.0, 200.0),
+            target,
+            Subtype::BasicShot,
+        );
+        simulation.shots.push(shot);
+        simulation.handle_collision();
+
+        assert!(simulation.enemies.is_empty());
+        let position = Vec2::new(600.0, 200.0);
+        assert_eq!(
+            simulation.events,
+            vec![
+                GameEvent::ShotHitEnemy {
+                    shot: Subtype::BasicShot,
+                    enemy: Subtype::BossEnemy,
+                    position,
+                },
+                GameEvent::EnemyKilled {
+                    subtype: Subtype::BossEnemy,
+                    position,
+                },
+                GameEvent::BossDefeated {
+                    subtype: Subtype::BossEnemy,
+                    position,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_boss_health_follows_every_hit() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let mut simulation = Simulation::new(6, GameData::default());
+        assert!(simulation.boss().is_none());
+        let mut boss = create_boss(Subtype::BossEnemy, 8.0);
+        let position = Vec2::new(600.0, 200.0);
+        boss.position = position;
+        simulation.enemies.push(boss);
+        let target = Vec2::new(600.0, -100.0);
+        for subtype in [Subtype::BasicShot, Subtype::AsteroidShot] {
+            simulation
+                .shots
+                .push(create_shot(&mut rng, position, target, subtype));
+        }
+        simulation.handle_collision();
+        let (boss, full_life) = simulation.boss().unwrap();
+        assert_eq!((boss.life_points, full_life), (2.0, 8.0));
+    }
+
+    #[test]
+    fn test_bosses_take_turns() {
+        let mut simulation = Simulation::new(4, GameData::default());
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut spawned = Vec::new();
+        for score in [39.5, 79.5, 119.5] {
+            simulation.current_score = score;
+            simulation.step();
+            let (_, full_life) = simulation.boss().unwrap();
+            let boss = simulation
+                .enemies
+                .iter_mut()
+                .find(|enemy| matches!(enemy.subtag, Subtype::BossEnemy | Subtype::TankEnemy))
+                .unwrap();
+            spawned.push((boss.subtag, full_life));
+            // one more hit finishes it, out of the second in which bosses spawn
+            boss.life_points = 1.0;
+            simulation.current_score += 1.0;
+            let position = boss.position;
+            simulation.shots.push(create_shot(
+                &mut rng,
+                position,
+                position - Vec2::new(0.0, 100.0),
+                Subtype::BasicShot,
+            ));
+            simulation.step();
+            assert!(simulation
+                .events
+                .iter()
+                .any(|event| matches!(event, GameEvent::BossDefeated { .. })));
+            assert!(simulation.boss().is_none());
+        }
+        assert_eq!(
+            spawned,
+            vec![
+                (Subtype::BossEnemy, 8.0),
+                (Subtype::TankEnemy, 20.0),
+                (Subtype::BossEnemy, 8.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_boss_warning_lasts_until_it_arrives() {
+        // the crawling tank takes much longer than the old fixed warning of three seconds
+        for (subtag, at_least, at_most) in [
+            (Subtype::BossEnemy, 0.5, 3.0),
+            (Subtype::TankEnemy, 3.0, 20.0),
+        ] {
+            let mut simulation = Simulation::new(9, GameData::default());
+            simulation.invulnerable = f32::MAX;
+            assert!(!simulation.boss_arriving());
+            simulation.enemies.push(create_boss(subtag, 20.0));
+            let mut seconds = 0.0;
+            while simulation.boss_arriving() && seconds < at_most {
+                simulation.step();
+                seconds += FIXED_DT;
+            }
+            assert!(
+                seconds > at_least,
+                "{:?} arrived after {}s",
+                subtag,
+                seconds
+            );
+            assert!(!simulation.boss_arriving(), "{:?} still arriving", subtag);
+        }
+    }
+
+    #[test]
+    fn test_tank_fires_wide_spread() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let mut tank = create_boss(Subtype::TankEnemy, 20.0);
+        let mut player = Actor::new();
+        player.position = Vec2::new(tank.position.x, screen_size().y - 100.0);
+        let mut shots = Vec::new();
+        tank_enemy_behaviour(&mut tank, &mut player, &mut shots, &mut rng, 0.1);
+        assert!(shots.is_empty());
+
+        tank.velocity.y = TANK_FIRE_INTERVAL;
+        tank_enemy_behaviour(&mut tank, &mut player, &mut shots, &mut rng, 0.1);
+        assert_eq!(shots.len(), TANK_SPREAD_SHOTS);
+        assert!(shots.iter().all(|shot| shot.subtag == Subtype::EnemyShot));
+        let angle = |shot: &Actor| {
+            let direction = shot.desired_pos - shot.position;
+            direction.x.atan2(direction.y)
+        };
+        let first = angle(&shots[0]);
+        let last = angle(&shots[TANK_SPREAD_SHOTS - 1]);
+        assert!((first - last).abs() > 1.5);
+        // the middle shot goes straight at the player
+        assert!(angle(&shots[TANK_SPREAD_SHOTS / 2]).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_enemy_catalog_loads_shipped_file() {
+        let catalog = GameData::default().enemies;
+        assert_eq!(catalog.archetypes.len(), 2);
+        assert_eq!(catalog.get(1).behaviour, BehaviourKind::Kamikaze);
+        let mut rng = StdRng::seed_from_u64(8);
+        let mut picked = [0; 2];
+        for _ in 0..1000 {
+            picked[catalog.pick(&mut rng)] += 1;
+        }
+        assert!(picked[0] > 400 && picked[1] > 400);
+    }
+
+    #[test]
+    fn test_kamikaze_steering() {
+        let catalog = GameData::default().enemies;
+        let steering = catalog.get(1).steering;
+        assert_eq!(steering, Steering::default());
+        let start = Vec2::new(600.0, 100.0);
+        let mut kamikaze = create_enemy(&catalog, 1, start, start, 0.0, 0.0);
+        let dt = 1.0 / 60.0;
+
+        // from rest it points at the target and speeds up, never past the top speed
+        let below = Vec2::new(600.0, 900.0);
+        kamikaze_behaviour(&mut kamikaze, below, &steering, dt);
+        assert!((kamikaze.velocity.length() - steering.acceleration * dt).abs() < 1e-3);
+        assert!(kamikaze.rotation.abs() < 1e-3);
+        for _ in 0..120 {
+            kamikaze_behaviour(&mut kamikaze, below, &steering, dt);
+        }
+        assert!((kamikaze.velocity.length() - steering.max_speed).abs() < 1e-3);
+
+        // a target suddenly off to the side is turned to gradually
+        let heading = kamikaze.velocity.normalize();
+        let side = kamikaze.position + Vec2::new(300.0, 0.0);
+        kamikaze_behaviour(&mut kamikaze, side, &steering, dt);
+        let turned = heading.angle_between(kamikaze.velocity.normalize());
+        assert!((turned.abs() - steering.turn_rate * dt).abs() < 1e-3);
+        let facing = Vec2::from_angle(kamikaze.rotation).rotate(Vec2::Y);
+        assert!((facing - kamikaze.velocity.normalize()).length() < 1e-3);
+
+        // so a last moment sidestep makes it fly past
+        let mut chaser = create_enemy(&catalog, 1, start, start, 0.0, 0.0);
+        chaser.velocity = Vec2::new(0.0, steering.max_speed);
+        let mut player = Vec2::new(600.0, 400.0);
+        let mut closest = f32::MAX;
+        for _ in 0..120 {
+            if (player - chaser.position).length() < 100.0 {
+                player.x = 700.0;
+            }
+            kamikaze_behaviour(&mut chaser, player, &steering, dt);
+            closest = closest.min((player - chaser.position).length());
+        }
+        assert!(closest > 40.0);
+
+        // the speed bonus is the one of its spawn, later ones don't speed it up any more
+        let fast = create_enemy(&catalog, 1, start, start, 3.5, 0.0);
+        assert_eq!(fast.speed_bonus, 3.5);
+        let mut simulation = Simulation::new(2, GameData::default());
+        // kept alive while the waves raise the bonus
+        simulation.invulnerable = f32::MAX;
+        for _ in 0..60 * 20 {
+            simulation.step();
+        }
+        assert!(!simulation.game_over && simulation.statistics.waves_reached > 0);
+        let far = Vec2::new(100.0, -2000.0);
+        simulation.enemies = vec![create_enemy(&catalog, 1, far, far, 0.0, 0.0)];
+        for _ in 0..120 {
+            simulation.step();
+        }
+        let kamikaze = &simulation.enemies[0];
+        assert!(kamikaze.velocity.length() <= steering.max_speed + 1e-3);
+    }
+
+    #[test]
+    fn test_game_data_loads_through_any_reader() {
+        let read = |file: &str| {
+            std::fs::read_to_string(format!("resources{}", file)).map_err(|e| e.to_string())
+        };
+        assert_eq!(GameData::load(read).unwrap(), GameData::default());
+        let error = GameData::load(|file: &str| match file {
+            "/waves.toml" => Err("missing".to_string()),
+            _ => read(file),
+        })
+        .unwrap_err();
+        assert_eq!(error.file, "/waves.toml");
+        assert_eq!(error.message, "missing");
+    }
+
+    #[test]
+    fn test_enemy_catalog_reports_error_line() {
+        let text = "[[enemy]]
+name = \"basic\"
+sprite = \"/enemy_ship_01.png\"
+hitbox = 22.0
+base_hp = 0.7
+hp_per_score = 0.01
+speed = 7.0
+behaviour = \"basic\"
+weight = 1
+
+[[enemy]]
+name = \"broken\"
+sprite = \"/enemy_ship_01.png\"
+hitbox = -1.0
+base_hp = 0.7
+hp_per_score = 0.01
+speed = 7.0
+behaviour = \"basic\"
+weight = 1
+";
+        let error = EnemyCatalog::from_toml(text).unwrap_err();
+        assert_eq!(error.line, Some(14));
+
+        let error =
+            EnemyCatalog::from_toml(&text.replace("\"basic\"\nweight", "\"sniper\"\nweight"))
+                .unwrap_err();
+        assert_eq!(error.line, Some(8));
+    }
+
+    #[test]
+    fn test_waves_progress() {
+        let data = GameData::default();
+        let first_wave = data.waves[0].clone();
+        let mut director = WaveDirector::new(data.waves);
+        let mut spawns = Vec::new();
+        assert_eq!(director.wave_number(), 0);
+
+        let mut started = None;
+        while started.is_none() {
+            started = director.update(0.1, 0, &mut spawns);
+        }
+        assert_eq!(started, Some(1));
+        assert_eq!(director.phase(), WavePhase::Active(0.0));
+        assert_eq!(spawns.len(), first_wave.groups.len());
+
+        // a cleared wave ends once every group has spawned
+        let total: u32 = first_wave.groups.iter().map(|group| group.count).sum();
+        let mut elapsed = 0.0;
+        while spawns.len() < total as usize {
+            director.update(0.1, spawns.len(), &mut spawns);
+            elapsed += 0.1;
+            assert!(elapsed < first_wave.timeout);
+        }
+        director.update(0.1, 0, &mut spawns);
+        assert!(matches!(director.phase(), WavePhase::Waiting(_)));
+        assert_eq!(director.wave_number(), 1);
+    }
+
+    #[test]
+    fn test_wave_times_out() {
+        let data = GameData::default();
+        let timeout = data.waves[0].timeout;
+        let mut director = WaveDirector::new(data.waves);
+        let mut spawns = Vec::new();
+        while director.update(0.5, 1, &mut spawns).is_none() {}
+        let mut elapsed = 0.0;
+        while let WavePhase::Active(_) = director.phase() {
+            director.update(0.5, 1, &mut spawns);
+            elapsed += 0.5;
+        }
+        assert!(elapsed >= timeout && elapsed < timeout + 1.0);
+        while director.update(0.5, 1, &mut spawns).is_none() {}
+        assert_eq!(director.wave_number(), 2);
+    }
+
+    #[test]
+    fn test_simulation_reports_waves() {
+        let mut simulation = Simulation::new(3, GameData::default());
+        let mut started = Vec::new();
+        for _ in 0..60 * 10 {
+            simulation.step();
+            for event in &simulation.events {
+                if let GameEvent::WaveStarted { number } = event {
+                    started.push(*number);
+                }
+            }
+        }
+        assert_eq!(started.first(), Some(&1));
+        assert_eq!(simulation.waves.wave_number(), *started.last().unwrap());
+        assert!(!simulation.enemies.is_empty() || simulation.game_over);
+    }
+
+    #[test]
+    fn test_waves_reject_unknown_enemy() {
+        let data = GameData::default();
+        let text = "[[wave]]
+delay = 1.0
+timeout = 10.0
+
+[[wave.group]]
+enemy = \"basic\"
+count = 2
+edge = \"top\"
+interval = 1.0
+
+[[wave]]
+delay = 1.0
+timeout = 10.0
+
+[[wave.group]]
+enemy = \"ghost\"
+count = 2
+edge = \"top\"
+interval = 1.0
+";
+        let error = parse_waves(text, &data.enemies).unwrap_err();
+        assert_eq!(error.line, Some(16));
+        assert!(parse_waves(&text.replace("ghost", "kamikaze"), &data.enemies).is_ok());
+    }
+
+    #[test]
+    fn test_high_scores_rank_order() {
+        let mut table = HighScoreTable::default();
+        assert_eq!(
+            table.insert(HighScoreEntry::new("a", 10.0, 1, 10.0)),
+            Some(0)
+        );
+        assert_eq!(
+            table.insert(HighScoreEntry::new("b", 30.0, 2, 30.0)),
+            Some(0)
+        );
+        assert_eq!(
+            table.insert(HighScoreEntry::new("c", 20.0, 3, 20.0)),
+            Some(1)
+        );
+        assert_eq!(
+            table.insert(HighScoreEntry::new("d", 20.0, 4, 20.0)),
+            Some(2)
+        );
+        let names: Vec<_> = table.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "c", "d", "a"]);
+
+        for i in 0..MAX_HIGH_SCORES {
+            table.insert(HighScoreEntry::new("filler", 100.0 + i as f32, 5, 1.0));
+        }
+        assert_eq!(table.entries.len(), MAX_HIGH_SCORES);
+        assert_eq!(
+            table.insert(HighScoreEntry::new("late", 50.0, 6, 1.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_high_scores_file_handling() {
+        let directory = std::env::temp_dir().join(format!("highscores-{}", std::process::id()));
+        let path = directory.join("highscores.json");
+        assert_eq!(HighScoreTable::load(&path), HighScoreTable::default());
+
+        let mut table = HighScoreTable::default();
+        table.insert(HighScoreEntry::new("a", 12.5, 9, 12.5));
+        table.save(&path).unwrap();
+        assert_eq!(HighScoreTable::load(&path), table);
+
+        std::fs::write(&path, "{ not json").unwrap();
+        assert_eq!(HighScoreTable::load(&path), HighScoreTable::default());
+        assert!(path.with_extension("json.bak").exists());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_high_score_date() {
+        let mut entry = HighScoreEntry::new("a", 1.0, 1, 1.0);
+        entry.date = 1_700_000_000;
+        assert_eq!(entry.date_string(), "2023-11-14");
+        entry.date = 0;
+        assert_eq!(entry.date_string(), "1970-01-01");
+    }
+
+    #[test]
+    fn test_shipped_config_matches_defaults() {
+        let config = GameConfig::from_toml("config.toml", include_str!("../config.toml"), &[]);
+        assert_eq!(config, Ok(GameConfig::default()));
+        assert_eq!(
+            GameConfig::from_toml("empty", "", &[]),
+            Ok(GameConfig::default())
+        );
+    }
+
+    #[test]
+    fn test_config_overrides() {
+        let text = "[gameplay]\nplayer_speed = 500.0\n";
+        let overrides = [
+            "gameplay.player_speed=700".to_string(),
+            "gameplay.seed=3".to_string(),
+            "controls.layout=zqsd".to_string(),
+        ];
+        let config = GameConfig::from_toml("test", text, &overrides).unwrap();
+        assert_eq!(config.gameplay.player_speed, 700.0);
+        assert_eq!(config.gameplay.seed, Some(3));
+        assert_eq!(config.controls.layout, "zqsd");
+
+        let data = GameData::default().with_config(config);
+        let mut simulation = Simulation::new(1, data);
+        simulation.input.press(Action::MoveRight);
+        simulation.input.update_velocity();
+        assert_eq!(simulation.input.velocity.x, 700.0);
+        let overrides = ["controls.layout=dvorak".to_string()];
+        assert!(GameConfig::from_toml("test", "", &overrides).is_err());
+    }
+
+    #[test]
+    fn test_config_errors() {
+        let error = GameConfig::from_toml("test", "[window]\nwidht = 800.0\n", &[]).unwrap_err();
+        assert!(error.message.contains("widht"));
+        let error = GameConfig::from_toml("test", "[window]\nwidth = \n", &[]).unwrap_err();
+        assert_eq!(error.line, Some(2));
+        let overrides = ["gameplay.player_speed=-1".to_string()];
+        assert!(GameConfig::from_toml("test", "", &overrides).is_err());
+        let overrides = ["gameplay.player_speed".to_string()];
+        assert_eq!(
+            GameConfig::from_toml("test", "", &overrides)
+                .unwrap_err()
+                .file,
+            "command line"
+        );
+    }
+
+    #[test]
+    fn test_key_binding_layouts() {
+        let wasd = KeyBindings::preset("wasd").unwrap();
+        assert_eq!(wasd.action(KeyCode::W), Some(Action::MoveUp));
+        assert_eq!(wasd.action(KeyCode::Up), Some(Action::MoveUp));
+        assert_eq!(wasd.action(KeyCode::Z), None);
+        let azerty = KeyBindings::preset("zqsd").unwrap();
+        assert_eq!(azerty.action(KeyCode::Z), Some(Action::MoveUp));
+        assert_eq!(azerty.action(KeyCode::Q), Some(Action::MoveLeft));
+        assert_eq!(azerty.action(KeyCode::Escape), Some(Action::Pause));
+        let arrows = KeyBindings::preset("arrows").unwrap();
+        assert_eq!(arrows.action(KeyCode::A), None);
+        assert!(KeyBindings::preset("qwertz").is_none());
+    }
+
+    #[test]
+    fn test_key_bindings_file_handling() {
+        let directory = std::env::temp_dir().join(format!("keybindings-{}", std::process::id()));
+        let path = directory.join("keybindings.toml");
+        // the first start writes the preset out
+        let preset = KeyBindings::preset("zqsd").unwrap();
+        assert_eq!(KeyBindings::load(&path, "zqsd"), preset);
+        assert!(path.exists());
+
+        let mut edited = preset.clone();
+        edited.fire = vec![KeyCode::LControl, KeyCode::F];
+        edited.save(&path).unwrap();
+        assert_eq!(KeyBindings::load(&path, "wasd"), edited);
+        assert_eq!(edited.action(KeyCode::F), Some(Action::Fire));
+
+        let clash = std::fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"F\"", "\"Z\"");
+        assert!(KeyBindings::from_toml(&clash).unwrap_err().contains("Z"));
+        std::fs::write(&path, clash).unwrap();
+        assert_eq!(
+            KeyBindings::load(&path, "wasd"),
+            KeyBindings::preset("wasd").unwrap()
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_opposite_directions_last_pressed_wins() {
+        let mut input = InputState::new(&GameConfig::default());
+        input.press(Action::MoveLeft);
+        input.press(Action::MoveRight);
+        assert_eq!(input.movement(), Vec2::new(1.0, 0.0));
+        // letting go of the newer key goes back to the one still held
+        input.release(Action::MoveRight);
+        assert_eq!(input.movement(), Vec2::new(-1.0, 0.0));
+        input.press(Action::MoveRight);
+        input.release(Action::MoveLeft);
+        assert_eq!(input.movement(), Vec2::new(1.0, 0.0));
+        input.release(Action::MoveRight);
+        assert_eq!(input.movement(), Vec2::ZERO);
+
+        input.press(Action::MoveDown);
+        input.press(Action::MoveUp);
+        input.press(Action::MoveDown);
+        assert_eq!(input.movement(), Vec2::new(0.0, 1.0));
+        input.release(Action::MoveDown);
+        assert_eq!(input.movement(), Vec2::new(0.0, -1.0));
+    }
+
+    #[test]
+    fn test_diagonal_movement_is_normalized() {
+        let config = GameConfig::default();
+        let mut input = InputState::new(&config);
+        input.press(Action::MoveUp);
+        input.press(Action::MoveRight);
+        input.update_velocity();
+        assert!((input.velocity.length() - config.gameplay.player_speed).abs() < 0.01);
+        assert!(input.velocity.x > 0.0 && input.velocity.y < 0.0);
+
+        // firing and releasing keys that were never pressed leave movement alone
+        input.press(Action::Fire);
+        input.release(Action::MoveLeft);
+        input.release(Action::Fire);
+        assert!(!input.is_firing);
+        assert!((input.movement().length() - 1.0).abs() < 1e-6);
+        input.release(Action::MoveUp);
+        input.release(Action::MoveRight);
+        input.update_velocity();
+        assert_eq!(input.velocity, Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_gamepad_stick_deadzone() {
+        let mut gamepad = GamepadInput::new(0.2);
+        assert_eq!(gamepad.axis(Axis::LeftStickX, 0.15), None);
+        assert_eq!(gamepad.stick(), Vec2::ZERO);
+        // pushing up on the stick is positive, on screen that's negative y
+        assert_eq!(gamepad.axis(Axis::LeftStickY, 0.6), Some(Stick::Left));
+        let stick = gamepad.stick();
+        assert!(stick.y < 0.0 && stick.x > 0.0);
+        assert!(stick.length() < 0.6);
+        gamepad.axis(Axis::LeftStickX, 1.0);
+        gamepad.axis(Axis::LeftStickY, -1.0);
+        assert!((gamepad.stick().length() - 1.0).abs() < 1e-6);
+        // the right stick aims instead, the triggers do nothing
+        assert_eq!(gamepad.axis(Axis::RightStickX, 1.0), Some(Stick::Right));
+        assert_eq!(gamepad.aim(), Some(Vec2::new(1.0, 0.0)));
+        assert_eq!(gamepad.axis(Axis::LeftZ, 1.0), None);
+
+        let mut input = InputState::new(&GameConfig::default());
+        input.set_stick(gamepad.stick());
+        assert_eq!(input.movement(), gamepad.stick());
+        // held directions take over from the stick
+        input.press(Action::MoveLeft);
+        assert_eq!(input.movement(), Vec2::new(-1.0, 0.0));
+    }
+
+    #[test]
+    fn test_gamepad_buttons_and_unplugging() {
+        let mut gamepad = GamepadInput::new(0.2);
+        assert_eq!(gamepad.button_down(Button::South), Some(Action::Fire));
+        assert_eq!(
+            gamepad.button_down(Button::DPadLeft),
+            Some(Action::MoveLeft)
+        );
+        assert_eq!(gamepad.button_down(Button::Start), Some(Action::Pause));
+        assert_eq!(gamepad.button_up(Button::Start), Some(Action::Pause));
+        assert_eq!(gamepad.button_down(Button::Mode), None);
+        gamepad.axis(Axis::LeftStickX, 0.9);
+        assert_eq!(gamepad.disconnect(), vec![Action::Fire, Action::MoveLeft]);
+        assert_eq!(gamepad.stick(), Vec2::ZERO);
+        assert!(gamepad.disconnect().is_empty());
+    }
+
+    #[test]
+    fn test_replay_reproduces_stick_movement() {
+        let mut simulation = Simulation::new(8, GameData::default());
+        let mut replay = Replay::new(simulation.seed, GameMode::Survival, AimMode::Fixed);
+        for frame in 0..120 {
+            if frame == 10 || frame == 70 {
+                let direction = Vec2::new(0.5, frame as f32 / -100.0);
+                replay.record_stick(simulation.frame, direction);
+                simulation.input.set_stick(direction);
+            }
+            replay.record_frame();
+            simulation.step();
+        }
+        let loaded = Replay::from_json(&replay.to_json()).unwrap();
+        let replayed = run_replay(&loaded, GameData::default());
+        assert_eq!(replayed.player.position, simulation.player.position);
+    }
+
+    #[test]
+    fn test_replay_stops_at_its_end() {
+        let mut simulation = Simulation::new(5, GameData::default());
+        let mut replay = Replay::new(simulation.seed, GameMode::Survival, AimMode::Fixed);
+        replay.record_action(0, Action::Fire, true);
+        simulation.input.press(Action::Fire);
+        // quit long before the game is over
+        for _ in 0..60 {
+            replay.record_frame();
+            simulation.step();
+        }
+        let mut player = ReplayPlayer::new(replay);
+        let mut replayed = Simulation::new(player.seed(), GameData::default());
+        let mut events = 0;
+        while player.step(&mut replayed) {
+            events += replayed.events.len();
+        }
+        assert!(events > 0);
+        assert_eq!(replayed.frame, 60);
+        for _ in 0..10 {
+            assert!(!player.step(&mut replayed));
+            assert!(replayed.events.is_empty());
+        }
+        assert!(!replayed.game_over);
+    }
+
+    #[test]
+    fn test_replay_keeps_aim_mode() {
+        let mut data = GameData::default();
+        data.config.controls.aim = AimMode::Mouse;
+        let mut simulation = Simulation::new(6, data);
+        let mut replay = Replay::new(simulation.seed, GameMode::Survival, AimMode::Mouse);
+        let aim = simulation.player.position + Vec2::new(300.0, 0.0);
+        replay.record_aim(simulation.frame, aim);
+        simulation.input.set_aim(aim);
+        replay.record_action(simulation.frame, Action::Fire, true);
+        simulation.input.press(Action::Fire);
+        for _ in 0..120 {
+            replay.record_frame();
+            simulation.step();
+        }
+        // played back with the default config, which aims straight up
+        let loaded = Replay::from_json(&replay.to_json()).unwrap();
+        let replayed = run_replay(&loaded, GameData::default());
+        assert_eq!(replayed.data.config.controls.aim, AimMode::Mouse);
+        assert_eq!(replayed.player.rotation, simulation.player.rotation);
+        let directions = |simulation: &Simulation| -> Vec<Vec2> {
+            simulation
+                .shots
+                .iter()
+                .filter(|shot| shot.subtag == Subtype::BasicShot)
+                .map(|shot| shot.desired_pos - shot.position)
+                .collect()
+        };
+        assert!(!directions(&simulation).is_empty());
+        assert_eq!(directions(&replayed), directions(&simulation));
+    }
+
+    #[test]
+    fn test_aimed_shots_fan_around_the_aim() {
+        let mut data = GameData::default();
+        data.config.controls.aim = AimMode::Stick;
+        let mut simulation = Simulation::new(4, data);
+        simulation.arsenal.levels[0] = 3;
+        simulation.arsenal.timer = 1.0;
+        simulation.input.set_aim(Vec2::new(1.0, 0.0));
+        simulation.input.press(Action::Fire);
+        simulation.step();
+        assert!((simulation.player.rotation - std::f32::consts::FRAC_PI_2).abs() < 1e-5);
+        let shots: Vec<_> = simulation
+            .shots
+            .iter()
+            .filter(|shot| shot.subtag == Subtype::BasicShot)
+            .map(|shot| (shot.desired_pos - shot.position).normalize())
+            .collect();
+        assert_eq!(shots.len(), 3);
+        // the first shot goes straight along the aim, the others to either side of it
+        assert!(shots[0].abs_diff_eq(Vec2::new(1.0, 0.0), 1e-5));
+        assert!(shots[1].y < 0.0 && shots[2].y > 0.0);
+        assert!((shots[1].y + shots[2].y).abs() < 1e-5);
+
+        // the mouse aims at a point, from wherever the player is
+        let mut data = GameData::default();
+        data.config.controls.aim = AimMode::Mouse;
+        let mut simulation = Simulation::new(4, data);
+        // before the cursor moved the first time the ship and its shots point straight up
+        simulation.arsenal.timer = 1.0;
+        simulation.input.press(Action::Fire);
+        simulation.step();
+        assert_eq!(simulation.player.rotation, 0.0);
+        let shot = &simulation.shots[0];
+        let direction = (shot.desired_pos - shot.position).normalize();
+        assert!(direction.abs_diff_eq(Vec2::new(0.0, -1.0), 1e-5));
+        simulation.input.release(Action::Fire);
+        let below = simulation.player.position + Vec2::new(0.0, 200.0);
+        simulation.input.set_aim(below);
+        simulation.step();
+        assert!((simulation.player.rotation.abs() - std::f32::consts::PI).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_sound_manifest() {
+        let sounds = parse_sounds(include_str!("../resources/sounds.toml")).unwrap();
+        for event in [
+            SoundEvent::Fire,
+            SoundEvent::EnemyHit,
+            SoundEvent::Explosion,
+            SoundEvent::Pickup,
+            SoundEvent::PlayerDamage,
+            SoundEvent::BossSpawn,
+        ] {
+            assert!(sounds.iter().any(|sound| sound.event == event));
+        }
+        let text = "[[sound]]\nevent = \"fire\"\nfile = \"/a.wav\"\nvolume = 1.5\npitch = 1.0\nvoices = 1\n";
+        let error = parse_sounds(text).unwrap_err();
+        assert_eq!(error.line, Some(4));
+        let error = parse_sounds(&text.replace("fire", "boom")).unwrap_err();
+        assert_eq!(error.line, Some(2));
+    }
+
+    #[test]
+    fn test_sound_voice_cap() {
+        // (id, finished)
+        let mut voices = vec![(1, false), (2, true), (3, false)];
+        assert_eq!(make_room(&mut voices, 3, |voice| voice.1), None);
+        assert_eq!(voices, vec![(1, false), (3, false)]);
+        assert_eq!(make_room(&mut voices, 2, |voice| voice.1), Some((1, false)));
+        assert_eq!(voices, vec![(3, false)]);
+    }
+
+    #[test]
+    fn test_sound_without_audio_device() {
+        let definitions = parse_sounds(include_str!("../resources/sounds.toml")).unwrap();
+        let files = definitions
+            .iter()
+            .map(|definition| std::fs::read(format!("resources{}", definition.file)).unwrap())
+            .collect();
+        let mut sounds =
+            SoundManager::with_output(None, definitions.clone(), files, AudioConfig::default())
+                .unwrap();
+        assert!((sounds.volume_for(0.5) - 0.4).abs() < 1e-6);
+        sounds.toggle_mute();
+        assert_eq!(sounds.volume_for(0.5), 0.0);
+
+        // a whole run's worth of events plays silently
+        let mut simulation = Simulation::new(2, GameData::default());
+        simulation.input.press(Action::Fire);
+        let mut fired = false;
+        for _ in 0..120 {
+            simulation.step();
+            fired |= simulation
+                .events
+                .iter()
+                .any(|event| SoundEvent::for_game_event(event) == Some(SoundEvent::Fire));
+            sounds.play_events(&simulation.events);
+        }
+        assert!(fired);
+
+        let broken = vec![b"not a sound".to_vec(); definitions.len()];
+        assert!(
+            SoundManager::with_output(None, definitions, broken, AudioConfig::default()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_menu_navigation() {
+        let mut menu = Menu::new(3);
+        assert_eq!(menu.handle(Action::MoveUp), None);
+        assert_eq!(menu.selected, 2);
+        assert_eq!(menu.handle(Action::MoveDown), None);
+        assert_eq!(menu.selected, 0);
+        menu.handle(Action::MoveDown);
+        assert_eq!(
+            menu.handle(Action::MoveLeft),
+            Some(MenuChoice::Adjust(1, -1))
+        );
+        assert_eq!(menu.handle(Action::Confirm), Some(MenuChoice::Confirm(1)));
+        assert_eq!(menu.handle(Action::Fire), Some(MenuChoice::Confirm(1)));
+        assert_eq!(menu.handle(Action::Restart), None);
+    }
+
+    // writes what the stack did to it into a shared log
+    struct LoggingScene {
+        name: &'static str,
+        overlay: bool,
+        log: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Scene for LoggingScene {
+        fn draw(
+            &mut self,
+            _ctx: &mut ggez::Context,
+            _shared: &mut Shared,
+            _canvas: &mut ggez::graphics::Canvas,
+        ) {
+        }
+
+        fn action_down(&mut self, _: &mut Shared, _: Action, _: bool) -> Transition {
+            Transition::None
+        }
+
+        fn suspend(&mut self) {
+            self.log.borrow_mut().push(format!("suspend {}", self.name));
+        }
+
+        fn leave(&mut self) {
+            self.log.borrow_mut().push(format!("leave {}", self.name));
+        }
+
+        fn is_overlay(&self) -> bool {
+            self.overlay
+        }
+    }
+
+    #[test]
+    fn test_scene_stack_transitions() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let scene = |name, overlay| {
+            Box::new(LoggingScene {
+                name,
+                overlay,
+                log: log.clone(),
+            })
+        };
+        let mut stack = SceneStack::new(scene("title", false));
+        assert!(stack.apply(Transition::Reset(scene("playing", false))));
+        assert!(stack.apply(Transition::Push(scene("pause", true))));
+        assert_eq!(stack.visible().len(), 2);
+        assert!(stack.apply(Transition::Push(scene("settings", false))));
+        assert_eq!(stack.visible().len(), 1);
+        assert!(stack.apply(Transition::Pop));
+        assert!(stack.apply(Transition::Pop));
+        assert_eq!(stack.visible().len(), 1);
+        assert!(!stack.top().is_overlay());
+        assert!(!stack.apply(Transition::Quit));
+        assert!(!stack.apply(Transition::Pop));
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                "leave title",
+                "suspend playing",
+                "suspend pause",
+                "leave settings",
+                "leave pause",
+                "leave playing",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_interrupting_a_run_pauses_it() {
+        let playing = Playing::from_simulation(Simulation::new(3, GameData::default()));
+        let mut stack = SceneStack::new(Box::new(playing));
+        // only the run reports an aim mode, so it tells which scene is on top
+        assert!(stack.top().aim_mode().is_some());
+        let transition = stack.top().interrupted();
+        assert!(matches!(
+            &transition,
+            Transition::Push(pause) if pause.is_overlay() && pause.aim_mode().is_none()
+        ));
+        assert!(stack.apply(transition));
+        // the state steps the top scene only, the run stays frozen below the pause menu
+        assert!(stack.top().aim_mode().is_none());
+        assert_eq!(stack.visible().len(), 2);
+        // losing focus again while paused doesn't stack another pause
+        assert!(matches!(stack.top().interrupted(), Transition::None));
+        assert!(stack.apply(Transition::Pop));
+        assert!(stack.top().aim_mode().is_some());
+    }
+
+    #[test]
+    fn test_bullet_patterns() {
+        let origin = Vec2::new(100.0, 100.0);
+        let target = Vec2::new(100.0, 500.0);
+        let close = |a: Vec2, b: Vec2| (a - b).length() < 1e-3;
+
+        let radial = Pattern::Radial {
+            count: 4,
+            speed: 100.0,
+        }
+        .emit(origin, target);
+        let directions: Vec<Vec2> = radial.iter().map(|bullet| bullet.direction).collect();
+        assert!(close(directions[0], Vec2::Y));
+        assert!(close(directions[1], -Vec2::X));
+        assert!(close(directions[2], -Vec2::Y));
+        assert!(close(radial[0].position_at(0.5), Vec2::new(100.0, 150.0)));
+
+        let fan = Pattern::AimedFan {
+            count: 3,
+            spread: 1.0,
+            speed: 100.0,
+        }
+        .emit(origin, target);
+        assert!(close(fan[1].direction, Vec2::Y));
+        assert!((fan[0].direction.angle_between(fan[2].direction).abs() - 1.0).abs() < 1e-3);
+
+        // every bullet of a spiral leaves later and further around
+        let spiral = Pattern::Spiral {
+            count: 3,
+            speed: 100.0,
+            angular_velocity: 2.0,
+            interval: 0.5,
+        }
+        .emit(origin, target);
+        assert_eq!(spiral[2].delay, 1.0);
+        assert!((spiral[0].direction.angle_between(spiral[2].direction) - 2.0).abs() < 1e-3);
+        assert_eq!(spiral[2].position_at(0.9), origin);
+
+        // a quarter wave in, the bullet is a full amplitude to the side of its line
+        let stream = Pattern::SineStream {
+            count: 2,
+            speed: 100.0,
+            interval: 0.25,
+            amplitude: 10.0,
+            frequency: 1.0,
+        }
+        .emit(origin, target);
+        assert!(close(stream[0].position_at(0.25), Vec2::new(90.0, 125.0)));
+        assert!(close(stream[1].position_at(0.25), origin));
+
+        let delayed = Pattern::Delayed {
+            delay: 1.0,
+            pattern: Box::new(Pattern::Radial {
+                count: 2,
+                speed: 100.0,
+            }),
+        }
+        .emit(origin, target);
+        assert_eq!(delayed.len(), 2);
+        assert_eq!(delayed[1].position_at(1.0), origin);
+        assert!(close(delayed[1].position_at(1.5), Vec2::new(100.0, 50.0)));
+
+        // shots fired from a pattern follow it
+        let mut shots = Vec::new();
+        Pattern::AimedFan {
+            count: 2,
+            spread: 0.5,
+            speed: 100.0,
+        }
+        .fire(origin, target, Subtype::BossShot, &mut shots);
+        update_shot_movement(&mut shots, 0.5);
+        for shot in &shots {
+            let bullet = shot.bullet.unwrap();
+            assert_eq!(shot.position, bullet.position_at(0.5));
+            assert_eq!(shot.subtag, Subtype::BossShot);
+        }
+    }
+
+    #[test]
+    fn test_formation_marches() {
+        let catalog = GameData::default().enemies;
+        let mut formation = Formation::new();
+        let mut invaders = formation.spawn(&catalog, 0.0);
+        assert_eq!(invaders.len(), FORMATION_ROWS * FORMATION_COLUMNS);
+        assert!(invaders.iter().all(is_invader));
+
+        // only the bottom row may fire, and whoever stands behind a dead alien takes over
+        let front = front_row(&invaders);
+        assert_eq!(front.len(), FORMATION_COLUMNS);
+        let bottom = invaders[front[0]].position.y;
+        assert!(front.iter().all(|&i| invaders[i].position.y == bottom));
+        invaders.remove(front[0]);
+        assert_eq!(front_row(&invaders).len(), FORMATION_COLUMNS);
+        assert!(front_row(&invaders)
+            .iter()
+            .any(|&i| invaders[i].position.y < bottom));
+
+        // nothing moves until a step is due, then all move sideways together
+        let start = invaders[0].position;
+        formation.update(&mut invaders, 0.1);
+        assert_eq!(invaders[0].position, start);
+        let full_step = formation.step_interval(invaders.len());
+        formation.update(&mut invaders, full_step);
+        assert_eq!(invaders[0].position, start + Vec2::new(15.0, 0.0));
+
+        // at the edge they drop and turn around
+        let mut steps = 0;
+        while invaders[0].position.y == start.y {
+            formation.update(&mut invaders, 1.0);
+            steps += 1;
+            assert!(steps < 100);
+        }
+        let dropped = invaders[0].position;
+        assert_eq!(dropped.y, start.y + 30.0);
+        formation.update(&mut invaders, 1.0);
+        assert_eq!(invaders[0].position, dropped - Vec2::new(15.0, 0.0));
+
+        // and speed up as they're shot down
+        assert!(formation.step_interval(1) < formation.step_interval(invaders.len()) / 5.0);
+    }
+
+    #[test]
+    fn test_formation_mode_runs() {
+        let mut simulation = Simulation::new(9, GameData::default()).with_mode(GameMode::Formation);
+        simulation.step();
+        assert_eq!(simulation.enemies.len(), FORMATION_ROWS * FORMATION_COLUMNS);
+        assert!(simulation
+            .events
+            .contains(&GameEvent::WaveStarted { number: 1 }));
+        for _ in 0..600 {
+            simulation.step();
+        }
+        // no waves, kamikazes or bosses, only the formation and its shots
+        assert!(simulation.enemies.iter().all(is_invader));
+        assert!(simulation
+            .shots
+            .iter()
+            .any(|shot| shot.subtag == Subtype::EnemyShot));
+
+        // a cleared formation is followed by the next one
+        simulation.enemies.clear();
+        simulation.step();
+        assert!(simulation
+            .events
+            .contains(&GameEvent::WaveStarted { number: 2 }));
+
+        // aliens reaching the ground end the run, whatever lives are left
+        assert!(simulation.lives > 1);
+        for invader in &mut simulation.enemies {
+            invader.position.y = screen_size().y - 100.0;
+        }
+        simulation.step();
+        assert!(simulation.game_over);
+        assert_eq!(simulation.lives, 0);
+        assert!(!simulation.is_player_alive());
+        assert!(simulation
+            .events
+            .iter()
+            .any(|event| matches!(event, GameEvent::PlayerKilled { .. })));
+    }
+
+    #[test]
+    fn test_bunker_erosion() {
+        let solid = |bunker: &Bunker| bunker.mask.iter().filter(|&&cell| cell).count();
+        let mut bunker = Bunker::new(Vec2::new(100.0, 500.0));
+        assert_eq!(bunker.mask.len(), BUNKER_WIDTH * BUNKER_HEIGHT);
+        // bevelled corners and the arch underneath
+        assert!(!bunker.is_solid(0, 0));
+        assert!(!bunker.is_solid(BUNKER_WIDTH / 2, BUNKER_HEIGHT - 1));
+        assert!(bunker.is_solid(BUNKER_WIDTH / 2, 0));
+        let full = solid(&bunker);
+        bunker.changed = false;
+
+        // a shot passing over the whole bunker in one step still hits its top edge
+        let column = 100.0 + 11.5 * BUNKER_PIXEL;
+        let above = Vec2::new(column, 400.0);
+        let below = Vec2::new(column, 700.0);
+        assert_eq!(bunker.hit(above, below), Some((11, 0)));
+        assert_eq!(bunker.hit(above, above + Vec2::new(0.0, 50.0)), None);
+        let removed = bunker.carve(11, 0);
+        assert!(removed > 0);
+        assert_eq!(solid(&bunker), full - removed);
+        assert!(bunker.changed);
+
+        // the next shot down the same column goes deeper, from below it's the other way round
+        let (_, depth) = bunker.hit(above, below).unwrap();
+        assert!(depth > 0);
+        let (_, from_below) = bunker.hit(below, above).unwrap();
+        assert!(from_below > depth);
+
+        // shooting the same hole again and again wears a tunnel right through
+        for _ in 0..20 {
+            if let Some((x, y)) = bunker.hit(above, below) {
+                bunker.carve(x, y);
+            }
+        }
+        assert_eq!(bunker.hit(above, below), None);
+        bunker.changed = false;
+        assert_eq!(bunker.carve(11, 0), 0);
+        assert!(!bunker.changed);
+        assert_eq!(bunker.to_rgba().len(), BUNKER_WIDTH * BUNKER_HEIGHT * 4);
+    }
+
+    #[test]
+    fn test_bunkers_stop_shots() {
+        let mut simulation = Simulation::new(9, GameData::default()).with_mode(GameMode::Formation);
+        simulation.step();
+        assert_eq!(simulation.bunkers.len(), 4);
+        // the ship starts underneath them, out of the aliens' reach
+        for bunker in &simulation.bunkers {
+            let bottom = bunker.position.y + BUNKER_HEIGHT as f32 * BUNKER_PIXEL;
+            assert!(simulation.player.position.y > bottom);
+        }
+        let bunker = &simulation.bunkers[0];
+        let above = bunker.position + Vec2::new(40.0, -30.0);
+        let below = bunker.position + Vec2::new(40.0, 100.0);
+        let full = bunker.mask.clone();
+        let mut rng = StdRng::seed_from_u64(9);
+        simulation.shots = vec![
+            create_shot(&mut rng, above, below, Subtype::EnemyShot),
+            create_shot(&mut rng, below, above, Subtype::BasicShot),
+        ];
+        for shot in &mut simulation.shots {
+            shot.position += (shot.desired_pos - shot.position) * 0.5;
+        }
+        simulation.handle_collision();
+        assert!(simulation.shots.is_empty());
+        assert_ne!(simulation.bunkers[0].mask, full);
+    }
+
+    #[test]
+    fn test_weapon_catalog() {
+        let catalog = GameData::default().weapons;
+        let kinds: Vec<_> = catalog.weapons.iter().map(|weapon| weapon.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                WeaponKind::Spread,
+                WeaponKind::Rapid,
+                WeaponKind::Laser,
+                WeaponKind::Missile
+            ]
+        );
+        assert_eq!(catalog.weapons[3].sprite, "/rocket.png");
+
+        let text = include_str!("../resources/weapons.toml");
+        let error = WeaponCatalog::from_toml(&text.replace("\"laser\"", "\"flamer\"")).unwrap_err();
+        let line = text.lines().position(|line| line == "kind = \"laser\"");
+        assert_eq!(error.line, line.map(|index| index + 1));
+        assert!(WeaponCatalog::from_toml(&text.replace("max_level = 3", "max_level = 0")).is_err());
+    }
+
+    #[test]
+    fn test_arsenal_switches_and_upgrades() {
+        let mut arsenal = Arsenal::new(&GameData::default().weapons);
+        let origin = Vec2::new(600.0, 900.0);
+        // the first volley needs the cooldown to run out, holding the trigger is not enough
+        assert!(arsenal.update(0.1, true, origin, None).is_empty());
+        assert_eq!(arsenal.update(1.0, true, origin, None).len(), 1);
+        assert!(arsenal.update(1.0, false, origin, None).is_empty());
+
+        // every pickup is one more shot for the spread gun, up to its last level
+        for _ in 0..10 {
+            arsenal.upgrade();
+        }
+        assert_eq!(arsenal.level(), 5);
+        assert!(!arsenal.upgrade());
+        assert_eq!(arsenal.update(0.0, true, origin, None).len(), 5);
+
+        // the other weapons keep their own levels and cooldowns
+        arsenal.switch();
+        assert_eq!(arsenal.level(), 1);
+        let rapid = arsenal.cooldown();
+        arsenal.upgrade();
+        assert!(arsenal.cooldown() < rapid);
+        arsenal.reduce_cooldowns(10.0);
+        assert!(arsenal.cooldown() > 0.0);
+
+        arsenal.switch();
+        arsenal.switch();
+        arsenal.upgrade();
+        let missiles = arsenal.update(10.0, true, origin, None);
+        assert_eq!(missiles.len(), 2);
+        assert!(missiles
+            .iter()
+            .all(|missile| missile.subtag == Subtype::MissileShot && missile.archetype == 3));
+        assert!((missiles[0].position.x - missiles[1].position.x).abs() > 1.0);
+
+        arsenal.switch();
+        assert_eq!(arsenal.weapon().stats().name, "Spread Gun");
+        assert_eq!(arsenal.level(), 5);
+    }
+
+    #[test]
+    fn test_laser_pierces_enemies() {
+        let data = GameData::default();
+        let mut simulation = Simulation::new(12, data);
+        simulation.input.press(Action::SwitchWeapon);
+        simulation.input.press(Action::SwitchWeapon);
+        simulation.step();
+        assert_eq!(simulation.arsenal.current, 1);
+        simulation.input.press(Action::SwitchWeapon);
+        simulation.step();
+        assert_eq!(simulation.arsenal.weapon().stats().kind, WeaponKind::Laser);
+
+        // two enemies in a column above the ship, a laser goes through both
+        let catalog = &simulation.data.enemies;
+        let player = simulation.player.position;
+        let mut enemies = Vec::new();
+        for height in [200.0, 300.0] {
+            let position = player - Vec2::new(0.0, height);
+            let mut enemy = create_enemy(catalog, 0, position, position, 0.0, 0.0);
+            // invaders stand still outside the formation mode
+            enemy.subtag = Subtype::InvaderEnemy;
+            enemy.life_points = 10.0;
+            enemies.push(enemy);
+        }
+        simulation.enemies = enemies;
+        simulation.shots.clear();
+        simulation.arsenal.timer = 10.0;
+        simulation.input.press(Action::Fire);
+        let mut hits = Vec::new();
+        for _ in 0..30 {
+            simulation.step();
+            simulation.input.release(Action::Fire);
+            for event in &simulation.events {
+                if let GameEvent::ShotHitEnemy { shot, position, .. } = event {
+                    hits.push((*shot, position.y));
+                }
+            }
+        }
+        assert!(hits.iter().all(|(shot, _)| *shot == Subtype::LaserShot));
+        assert!(hits.iter().any(|(_, y)| *y > player.y - 250.0));
+        assert!(hits.iter().any(|(_, y)| *y < player.y - 250.0));
+    }
 }
@@ -0,0 +1,247 @@
+use serde::Deserialize;
+
+use crate::data::{line_of_entry, line_of_field, parse_toml, DataError};
+use crate::enemies::EnemyCatalog;
+use crate::helpers::Edge;
+
+pub const WAVES_FILE: &str = "/waves.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaveGroup {
+    // None picks a random archetype by spawn weight every time
+    pub archetype: Option<usize>,
+    pub count: u32,
+    // None spawns on a random edge every time
+    pub edge: Option<Edge>,
+    pub interval: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wave {
+    pub delay: f32,
+    pub timeout: f32,
+    pub groups: Vec<WaveGroup>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawGroup {
+    enemy: String,
+    count: u32,
+    edge: String,
+    interval: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawWave {
+    delay: f32,
+    timeout: f32,
+    #[serde(default)]
+    group: Vec<RawGroup>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WaveFile {
+    wave: Vec<RawWave>,
+}
+
+// enemy names are resolved against the catalog while loading, so a typo fails at startup
+pub fn parse_waves(text: &str, catalog: &EnemyCatalog) -> Result<Vec<Wave>, DataError> {
+    let file: WaveFile = parse_toml(WAVES_FILE, text)?;
+    let error = |header: &str, index: usize, key: &str, message: String| DataError {
+        file: WAVES_FILE.to_string(),
+        line: line_of_field(text, header, index, key),
+        message,
+    };
+    if file.wave.is_empty() {
+        return Err(DataError {
+            file: WAVES_FILE.to_string(),
+            line: None,
+            message: "at least one wave is needed".to_string(),
+        });
+    }
+    let mut waves = Vec::new();
+    // groups are numbered across the whole file, that's how their headers appear in the text
+    let mut group_index = 0;
+    for (wave_index, raw) in file.wave.into_iter().enumerate() {
+        if raw.delay < 0.0 {
+            let message = "delay can't be negative".to_string();
+            return Err(error("wave", wave_index, "delay", message));
+        }
+        if raw.timeout <= 0.0 {
+            let message = "timeout must be positive".to_string();
+            return Err(error("wave", wave_index, "timeout", message));
+        }
+        if raw.group.is_empty() {
+            return Err(DataError {
+                file: WAVES_FILE.to_string(),
+                line: line_of_entry(text, "wave", wave_index),
+                message: "a wave needs at least one [[wave.group]]".to_string(),
+            });
+        }
+        let mut groups = Vec::new();
+        for group in raw.group {
+            let archetype = match group.enemy.as_str() {
+                "random" => None,
+                name => match catalog.archetypes.iter().position(|a| a.name == name) {
+                    Some(index) => Some(index),
+                    None => {
+                        let message = format!("unknown enemy '{}'", name);
+                        return Err(error("wave.group", group_index, "enemy", message));
+                    }
+                },
+            };
+            let edge = match group.edge.as_str() {
+                "top" => Some(Edge::Top),
+                "bottom" => Some(Edge::Bottom),
+                "left" => Some(Edge::Left),
+                "right" => Some(Edge::Right),
+                "any" => None,
+                _ => {
+                    let message = format!("unknown edge '{}'", group.edge);
+                    return Err(error("wave.group", group_index, "edge", message));
+                }
+            };
+            if group.count == 0 {
+                let message = "count must be at least 1".to_string();
+                return Err(error("wave.group", group_index, "count", message));
+            }
+            if group.interval < 0.0 {
+                let message = "interval can't be negative".to_string();
+                return Err(error("wave.group", group_index, "interval", message));
+            }
+            groups.push(WaveGroup {
+                archetype,
+                count: group.count,
+                edge,
+                interval: group.interval,
+            });
+            group_index += 1;
+        }
+        waves.push(Wave {
+            delay: raw.delay,
+            timeout: raw.timeout,
+            groups,
+        });
+    }
+    Ok(waves)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpawnOrder {
+    pub archetype: Option<usize>,
+    pub edge: Option<Edge>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WavePhase {
+    // calm before the next wave, seconds left
+    Waiting(f32),
+    // a wave is running, seconds since it started
+    Active(f32),
+}
+
+// walks through the waves, handing out spawn orders and deciding when a wave is over
+pub struct WaveDirector {
+    waves: Vec<Wave>,
+    current: usize,
+    // how many waves were started so far, keeps counting when the list repeats
+    wave_number: u32,
+    phase: WavePhase,
+    // (spawned, seconds since the last spawn) for every group of the current wave
+    groups: Vec<(u32, f32)>,
+}
+
+impl WaveDirector {
+    pub fn new(waves: Vec<Wave>) -> WaveDirector {
+        let phase = WavePhase::Waiting(waves[0].delay);
+        WaveDirector {
+            waves,
+            current: 0,
+            wave_number: 0,
+            phase,
+            groups: Vec::new(),
+        }
+    }
+
+    pub fn wave_number(&self) -> u32 {
+        self.wave_number
+    }
+
+    pub fn phase(&self) -> WavePhase {
+        self.phase
+    }
+
+    pub fn current_wave(&self) -> &Wave {
+        &self.waves[self.current]
+    }
+
+    fn all_spawned(&self) -> bool {
+        self.current_wave()
+            .groups
+            .iter()
+            .zip(&self.groups)
+            .all(|(group, (spawned, _))| *spawned >= group.count)
+    }
+
+    // returns the new wave number when a wave starts
+    pub fn update(
+        &mut self,
+        dt: f32,
+        enemies_alive: usize,
+        spawns: &mut Vec<SpawnOrder>,
+    ) -> Option<u32> {
+        match self.phase {
+            WavePhase::Waiting(remaining) => {
+                if remaining - dt > 0.0 {
+                    self.phase = WavePhase::Waiting(remaining - dt);
+                    return None;
+                }
+                self.wave_number += 1;
+                self.phase = WavePhase::Active(0.0);
+                // the first enemy of every group comes right away
+                self.groups = self
+                    .current_wave()
+                    .groups
+                    .iter()
+                    .map(|group| (0, group.interval))
+                    .collect();
+                self.spawn_due(spawns);
+                Some(self.wave_number)
+            }
+            WavePhase::Active(elapsed) => {
+                let elapsed = elapsed + dt;
+                self.phase = WavePhase::Active(elapsed);
+                for group in &mut self.groups {
+                    group.1 += dt;
+                }
+                let spawned_before = spawns.len();
+                self.spawn_due(spawns);
+                // enemies handed out just now aren't counted in `enemies_alive` yet
+                let cleared =
+                    self.all_spawned() && enemies_alive == 0 && spawns.len() == spawned_before;
+                if cleared || elapsed >= self.current_wave().timeout {
+                    self.current = (self.current + 1) % self.waves.len();
+                    self.phase = WavePhase::Waiting(self.current_wave().delay);
+                }
+                None
+            }
+        }
+    }
+
+    fn spawn_due(&mut self, spawns: &mut Vec<SpawnOrder>) {
+        let wave = &self.waves[self.current];
+        for (group, (spawned, timer)) in wave.groups.iter().zip(&mut self.groups) {
+            while *spawned < group.count && *timer >= group.interval {
+                *timer -= group.interval;
+                *spawned += 1;
+                spawns.push(SpawnOrder {
+                    archetype: group.archetype,
+                    edge: group.edge,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,333 @@
+use ggez::glam::Vec2;
+use serde::Deserialize;
+
+use crate::actors::{create_player_shot, Actor, Subtype};
+use crate::data::{line_of_field, parse_toml, DataError};
+use crate::screen_size;
+
+pub const WEAPONS_FILE: &str = "/weapons.toml";
+
+// radians between two neighbouring shots of an aimed spread
+const AIM_SPREAD: f32 = 0.15;
+// pixels between two missiles of one volley
+const MISSILE_SPACING: f32 = 14.0;
+// every level of the rapid-fire gun takes this share off its cooldown
+const RAPID_LEVEL_FACTOR: f32 = 0.8;
+// reload pickups never bring a cooldown below this share of the weapon's own
+const MIN_COOLDOWN_SHARE: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WeaponKind {
+    Spread,
+    Rapid,
+    Laser,
+    Missile,
+}
+
+impl WeaponKind {
+    fn from_name(name: &str) -> Option<WeaponKind> {
+        match name {
+            "spread" => Some(WeaponKind::Spread),
+            "rapid" => Some(WeaponKind::Rapid),
+            "laser" => Some(WeaponKind::Laser),
+            "missile" => Some(WeaponKind::Missile),
+            _ => None,
+        }
+    }
+}
+
+// one entry of the weapons file, the kind decides what the numbers mean at higher levels
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeaponStats {
+    pub name: String,
+    pub kind: WeaponKind,
+    pub sprite: String,
+    // seconds between two volleys at level 1
+    pub cooldown: f32,
+    pub speed: f32,
+    pub damage: f32,
+    pub max_level: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawWeapon {
+    name: String,
+    kind: String,
+    sprite: String,
+    cooldown: f32,
+    speed: f32,
+    damage: f32,
+    max_level: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WeaponFile {
+    weapon: Vec<RawWeapon>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeaponCatalog {
+    pub weapons: Vec<WeaponStats>,
+}
+
+impl WeaponCatalog {
+    pub fn from_toml(text: &str) -> Result<WeaponCatalog, DataError> {
+        let file: WeaponFile = parse_toml(WEAPONS_FILE, text)?;
+        let error = |index: usize, key: &str, message: String| DataError {
+            file: WEAPONS_FILE.to_string(),
+            line: line_of_field(text, "weapon", index, key),
+            message,
+        };
+        if file.weapon.is_empty() {
+            return Err(DataError {
+                file: WEAPONS_FILE.to_string(),
+                line: None,
+                message: "at least one weapon is needed".to_string(),
+            });
+        }
+        let mut weapons: Vec<WeaponStats> = Vec::new();
+        for (index, weapon) in file.weapon.into_iter().enumerate() {
+            if weapon.name.is_empty() {
+                return Err(error(index, "name", "name can't be empty".to_string()));
+            }
+            if weapons.iter().any(|w| w.name == weapon.name) {
+                let message = format!("duplicate weapon '{}'", weapon.name);
+                return Err(error(index, "name", message));
+            }
+            if !weapon.sprite.starts_with('/') {
+                let message = "sprite must start with '/'".to_string();
+                return Err(error(index, "sprite", message));
+            }
+            for (key, value) in [
+                ("cooldown", weapon.cooldown),
+                ("speed", weapon.speed),
+                ("damage", weapon.damage),
+            ] {
+                if value <= 0.0 {
+                    return Err(error(index, key, format!("{} must be positive", key)));
+                }
+            }
+            if weapon.max_level == 0 {
+                let message = "max_level must be at least 1".to_string();
+                return Err(error(index, "max_level", message));
+            }
+            let Some(kind) = WeaponKind::from_name(&weapon.kind) else {
+                let message = format!(
+                    "unknown kind '{}', expected \"spread\", \"rapid\", \"laser\" or \"missile\"",
+                    weapon.kind
+                );
+                return Err(error(index, "kind", message));
+            };
+            weapons.push(WeaponStats {
+                name: weapon.name,
+                kind,
+                sprite: weapon.sprite,
+                cooldown: weapon.cooldown,
+                speed: weapon.speed,
+                damage: weapon.damage,
+                max_level: weapon.max_level,
+            });
+        }
+        Ok(WeaponCatalog { weapons })
+    }
+}
+
+// a player weapon, the numbers come from the weapons file and the kind adds what levels do
+pub trait Weapon {
+    fn stats(&self) -> &WeaponStats;
+
+    // seconds between two volleys at this level, before reload pickups
+    fn cooldown(&self, _level: u32) -> f32 {
+        self.stats().cooldown
+    }
+
+    // the shots of one volley, `aim` is None when they just go up the screen
+    fn volley(&self, level: u32, origin: Vec2, aim: Option<Vec2>) -> Vec<Actor>;
+}
+
+// far along the aim, or straight up
+fn target_along(origin: Vec2, aim: Option<Vec2>) -> Vec2 {
+    origin + aim.unwrap_or(Vec2::new(0.0, -1.0)) * screen_size().length()
+}
+
+// one more shot every level, fanned around the aim or spread over the screen above
+pub struct SpreadGun(pub WeaponStats);
+
+impl Weapon for SpreadGun {
+    fn stats(&self) -> &WeaponStats {
+        &self.0
+    }
+
+    fn volley(&self, level: u32, origin: Vec2, aim: Option<Vec2>) -> Vec<Actor> {
+        let mut dest_x = origin.x;
+        (0..level as i32)
+            .map(|i| {
+                let dest_pos = match aim {
+                    Some(aim) => {
+                        // alternating sides: 0, -1, +1, -2, +2, ...
+                        let side = if i % 2 == 0 { i / 2 } else { -(i + 1) / 2 };
+                        let direction = Vec2::from_angle(side as f32 * AIM_SPREAD).rotate(aim);
+                        origin + direction * screen_size().length()
+                    }
+                    None => {
+                        if i % 2 == 0 {
+                            dest_x += (i as f32) * screen_size().x / 4.0;
+                        } else {
+                            dest_x -= (i as f32) * screen_size().x / 4.0;
+                        }
+                        Vec2::new(dest_x, -100.0)
+                    }
+                };
+                create_player_shot(origin, dest_pos, Subtype::BasicShot, &self.0)
+            })
+            .collect()
+    }
+}
+
+// a single shot that comes quicker every level
+pub struct RapidFire(pub WeaponStats);
+
+impl Weapon for RapidFire {
+    fn stats(&self) -> &WeaponStats {
+        &self.0
+    }
+
+    fn cooldown(&self, level: u32) -> f32 {
+        self.0.cooldown * RAPID_LEVEL_FACTOR.powi(level as i32 - 1)
+    }
+
+    fn volley(&self, _level: u32, origin: Vec2, aim: Option<Vec2>) -> Vec<Actor> {
+        let target = target_along(origin, aim);
+        vec![create_player_shot(
+            origin,
+            target,
+            Subtype::BasicShot,
+            &self.0,
+        )]
+    }
+}
+
+// goes through everything but bosses, hurting every enemy for each step it spends inside it,
+// every level adds the base damage again
+pub struct Laser(pub WeaponStats);
+
+impl Weapon for Laser {
+    fn stats(&self) -> &WeaponStats {
+        &self.0
+    }
+
+    fn volley(&self, level: u32, origin: Vec2, aim: Option<Vec2>) -> Vec<Actor> {
+        let target = target_along(origin, aim);
+        let mut shot = create_player_shot(origin, target, Subtype::LaserShot, &self.0);
+        shot.damage *= level as f32;
+        vec![shot]
+    }
+}
+
+// slow and heavy, one more missile side by side every level
+pub struct MissileLauncher(pub WeaponStats);
+
+impl Weapon for MissileLauncher {
+    fn stats(&self) -> &WeaponStats {
+        &self.0
+    }
+
+    fn volley(&self, level: u32, origin: Vec2, aim: Option<Vec2>) -> Vec<Actor> {
+        let target = target_along(origin, aim);
+        let across = (target - origin).normalize_or_zero().perp();
+        (0..level)
+            .map(|i| {
+                let offset = across * MISSILE_SPACING * (i as f32 - (level - 1) as f32 / 2.0);
+                create_player_shot(
+                    origin + offset,
+                    target + offset,
+                    Subtype::MissileShot,
+                    &self.0,
+                )
+            })
+            .collect()
+    }
+}
+
+pub fn build_weapon(stats: &WeaponStats) -> Box<dyn Weapon> {
+    let stats = stats.clone();
+    match stats.kind {
+        WeaponKind::Spread => Box::new(SpreadGun(stats)),
+        WeaponKind::Rapid => Box::new(RapidFire(stats)),
+        WeaponKind::Laser => Box::new(Laser(stats)),
+        WeaponKind::Missile => Box::new(MissileLauncher(stats)),
+    }
+}
+
+// every weapon of the catalog with its upgrade level, one of them in hand
+pub struct Arsenal {
+    weapons: Vec<Box<dyn Weapon>>,
+    // from 1 up to the weapon's max_level, kept when switching and respawning
+    pub levels: Vec<u32>,
+    pub current: usize,
+    // seconds since the last volley, shared by all weapons so switching doesn't skip a cooldown
+    pub timer: f32,
+    // seconds taken off every cooldown by reload pickups
+    reload_bonus: f32,
+}
+
+impl Arsenal {
+    pub fn new(catalog: &WeaponCatalog) -> Arsenal {
+        Arsenal {
+            weapons: catalog.weapons.iter().map(build_weapon).collect(),
+            levels: vec![1; catalog.weapons.len()],
+            current: 0,
+            timer: 0.5,
+            reload_bonus: 0.0,
+        }
+    }
+
+    pub fn weapon(&self) -> &dyn Weapon {
+        self.weapons[self.current].as_ref()
+    }
+
+    pub fn level(&self) -> u32 {
+        self.levels[self.current]
+    }
+
+    pub fn switch(&mut self) {
+        self.current = (self.current + 1) % self.weapons.len();
+    }
+
+    // returns false when the weapon in hand is already at its best
+    pub fn upgrade(&mut self) -> bool {
+        let max_level = self.weapon().stats().max_level;
+        let level = &mut self.levels[self.current];
+        if *level >= max_level {
+            return false;
+        }
+        *level += 1;
+        true
+    }
+
+    pub fn reduce_cooldowns(&mut self, seconds: f32) {
+        self.reload_bonus += seconds;
+    }
+
+    pub fn cooldown(&self) -> f32 {
+        let cooldown = self.weapon().cooldown(self.level());
+        (cooldown - self.reload_bonus).max(cooldown * MIN_COOLDOWN_SHARE)
+    }
+
+    // a volley whenever the trigger is held and the cooldown is over, shots remember the weapon
+    // they came from so they can be drawn with its sprite
+    pub fn update(&mut self, dt: f32, firing: bool, origin: Vec2, aim: Option<Vec2>) -> Vec<Actor> {
+        self.timer += dt;
+        if !firing || self.timer < self.cooldown() {
+            return Vec::new();
+        }
+        self.timer = 0.0;
+        let mut shots = self.weapon().volley(self.level(), origin, aim);
+        for shot in &mut shots {
+            shot.archetype = self.current;
+        }
+        shots
+    }
+}